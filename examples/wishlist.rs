@@ -13,6 +13,11 @@ pub const PUBLIC: i32 = 0;
 #[constant]
 pub const FRIENDS: i32 = 1;
 
+flux_rs::defs! {
+    // `friends(owner, viewer)` holds when `viewer` is a friend of `owner`.
+    fn friends(owner: int, viewer: int) -> bool;
+}
+
 #[trusted]
 mod schema {
     diesel::table! {
@@ -44,94 +49,50 @@ mod schema {
 }
 
 mod models {
-    use crate::schema;
+    use crate::{FRIENDS, PUBLIC};
     use diesel::{associations::Identifiable, Insertable, Queryable, Selectable};
     use flux_rs::*;
+    use rdiesel::RdieselModel;
+
+    /// The friendship relation is only known to Flux, so runtime checks conservatively assume
+    /// that nobody is a friend.
+    #[cfg(feature = "runtime-checks")]
+    fn friends(_owner: i32, _viewer: i32) -> bool {
+        false
+    }
 
     flux!(
-    #[derive(Clone, Queryable, Selectable, Identifiable)]
+    #[derive(Clone, Queryable, Selectable, Identifiable, RdieselModel)]
     #[diesel(table_name = crate::schema::users)]
-    pub struct User[id: int] {
+    pub struct User[id: int, username: str] {
         pub id: i32[id],
-        pub username: String,
+        #[rdiesel(index = username)]
+        pub username: String[username],
         pub password: String,
     }
 
-    #[derive(Queryable, Selectable, Identifiable)]
+    #[derive(Queryable, Selectable, Identifiable, RdieselModel)]
     #[diesel(table_name = crate::schema::wishes)]
-    // #[invariant(w == getJust(w.id).level)]
-    pub struct Wish[id: int, owner: int, price: int, level: int] {
+    pub struct Wish[id: int, owner: int, price: int, access_level: int] {
         pub id: i32[id],
         pub owner: i32[owner],
         pub title: String,
         pub price: i32[price],
         pub body: String,
-        pub access_level: i32[level],
+        pub access_level: i32[access_level],
     }
 
     #[derive(Clone, Insertable)]
     #[diesel(table_name = crate::schema::wishes)]
-    pub struct NewWish[owner: int, price: int, level: int] {
+    pub struct NewWish[owner: int, price: int, access_level: int] {
         pub owner: i32[owner],
         pub title: String,
         pub price: i32[price],
         pub body: String,
-        pub access_level: i32[level],
-    }
-
-    impl rdiesel::Row<User> for NewWish {
-        reft allow_insert(user: User, wish: NewWish) -> bool { user.id == wish.owner }
-    }
-
-    // Wish.id
-
-    impl rdiesel::Field<Wish, User> for schema::wishes::id {
-        reft allow_update(user: User, wish: Wish) -> bool {
-            false
-        }
-    }
-
-    impl rdiesel::Expr<Wish, i32> for schema::wishes::id {
-        reft eval(v: Self, wish: Wish) -> int { wish.price }
+        pub access_level: i32[access_level],
     }
 
-    // Wish.price
-
-    impl rdiesel::Field<Wish, User> for schema::wishes::price {
-        reft allow_update(user: User, wish: Wish) -> bool { user.id == wish.owner }
-    }
-
-    impl rdiesel::Expr<Wish, i32> for schema::wishes::price {
-        reft eval(v: Self, wish: Wish) -> int { wish.price }
-    }
-
-    // Wish.access_level
-
-    impl rdiesel::Field<Wish, User> for schema::wishes::access_level {
-        reft allow_update(user: User, wish: Wish) -> bool { user.id == wish.owner }
-    }
-
-    impl rdiesel::Expr<Wish, i32> for schema::wishes::access_level {
-        reft eval(v: Self, wish: Wish) -> int { wish.level }
-    }
-
-    // Wish.owner
-
-    impl rdiesel::Field<Wish, User> for schema::wishes::owner {
-        reft allow_update(user: User, wish: Wish) -> bool { false }
-    }
-
-    impl rdiesel::Expr<Wish, i32> for schema::wishes::owner {
-        reft eval(v: Self, wish: Wish) -> int { wish.owner }
-    }
-
-    // Wish.body
-
-    impl rdiesel::Field<Wish, User> for schema::wishes::body {
-        reft allow_update(user: User, wish: Wish) -> bool { user.id == wish.owner }
-    }
-
-    #[derive(Queryable, Selectable, Identifiable)]
+    #[derive(Queryable, Selectable, Identifiable, RdieselModel)]
     #[diesel(table_name = crate::schema::friendships)]
     pub struct Friendship[id: int, user1: int, user2: int, status: int] {
         pub id: i32[id],
@@ -139,47 +100,37 @@ mod models {
         pub user2: i32[user2],
         pub status: i32[status],
     }
+    );
 
-    // Friendship.id
-
-    impl rdiesel::Field<Friendship, User> for schema::friendships::id {
-        reft allow_update(user: User, f: Friendship) -> bool { false }
-    }
-
-    impl rdiesel::Expr<Friendship, i32> for schema::friendships::id {
-        reft eval(v: Self, f: Friendship) -> int { f.id }
-    }
-
-    // Friendship.user1
-
-    impl rdiesel::Field<Friendship, User> for schema::friendships::user1 {
-        reft allow_update(user: User, f: Friendship) -> bool { false }
-    }
-
-    impl rdiesel::Expr<Friendship, i32> for schema::friendships::user1 {
-        reft eval(v: Self, f: Friendship) -> int { f.user1 }
-    }
-
-    // Friendship.user2
-
-    impl rdiesel::Field<Friendship, User> for schema::friendships::user2 {
-        reft allow_update(user: User, f: Friendship) -> bool { false }
-    }
-
-    impl rdiesel::Expr<Friendship, i32> for schema::friendships::user2 {
-        reft eval(v: Self, f: Friendship) -> int { f.user2 }
-    }
+    rdiesel::policy! {
+        table crate::schema::users(User) for User {
+            read(id: i32, username: String): true,
+            update(username: String, password: String): user.id == row.id,
+        }
 
-    // Friendship.status
+        table crate::schema::wishes(Wish) for User {
+            read: user.id == row.owner
+                || row.access_level == PUBLIC
+                || (row.access_level == FRIENDS && friends(row.owner, user.id)),
+            insert(NewWish): user.id == row.owner,
+            update(id: i32, owner: i32): false,
+            update(title: String, price: i32, body: String, access_level: i32): user.id == row.owner,
+            delete: user.id == row.owner,
+            invariant: row.price >= 0 && (row.access_level == PUBLIC || row.access_level == FRIENDS),
+            invariant(price: i32): val >= 0,
+            invariant(access_level: i32): val == PUBLIC || val == FRIENDS,
+        }
 
-    impl rdiesel::Field<Friendship, User> for schema::friendships::status {
-        reft allow_update(user: User, f: Friendship) -> bool { false }
+        table crate::schema::friendships(Friendship) for User {
+            read: user.id == row.user1 || user.id == row.user2,
+            update(id: i32, user1: i32, user2: i32, status: i32): false,
+            invariant: friends(row.user1, row.user2),
+        }
     }
 
-    impl rdiesel::Expr<Friendship, i32> for schema::friendships::status {
-        reft eval(v: Self, f: Friendship) -> int { f.status }
-    }
-    );
+    rdiesel::assert_policy_coverage!(crate::schema::users::table, User, User);
+    rdiesel::assert_policy_coverage!(crate::schema::wishes::table, Wish, User);
+    rdiesel::assert_policy_coverage!(crate::schema::friendships::table, Friendship, User);
 
     impl diesel::associations::HasTable for NewWish {
         type Table = crate::schema::wishes::table;
@@ -197,7 +148,7 @@ pub struct Session {
 
 impl Session {
     fn into_context(self) -> Context {
-        Context::new(self)
+        Context::new(self).expect("failed to prepare the connection")
     }
 }
 
@@ -258,7 +209,7 @@ pub mod services {
         Session, FRIENDS, PUBLIC,
     };
     use flux_rs::*;
-    use rdiesel::{Expr, Field};
+    use rdiesel::{Expr, Field, ValueSet};
 
     #[sig(fn(bool[true]))]
     fn assert(_: bool) {}
@@ -274,24 +225,47 @@ pub mod services {
         .unwrap();
     }
 
+    pub fn user_index(sess: Session) {
+        let mut cx = sess.into_context();
+
+        let _: Vec<(i32, String)> = cx
+            .select_columns(users::id.gt(0), (users::id, users::username))
+            .unwrap();
+    }
+
+    pub fn user_search(sess: Session, pattern: String) {
+        let mut cx = sess.into_context();
+
+        let _: Vec<(i32, String)> = cx
+            .select_columns(users::username.like(pattern), (users::id, users::username))
+            .unwrap();
+    }
+
+    #[rocket::delete("/wish/<wish_id>")]
+    pub fn delete_wish(sess: Session, wish_id: i32) {
+        let mut cx = sess.into_context();
+
+        let auth_user = cx.auth_user();
+        let _ = cx.delete_where(wishes::id.eq(wish_id).and(wishes::owner.eq(auth_user.id)));
+    }
+
     #[rocket::get("/user/<user_id>")]
     pub fn user_show(sess: Session, user_id: i32) {
         let mut cx = sess.into_context();
 
         let auth_user = cx.auth_user();
 
-        let friends = cx
+        let friendship = cx
             .select_first(
                 friendships::user1
                     .eq(user_id)
                     .and(friendships::user2.eq(auth_user.id)),
             )
-            .unwrap()
-            .is_some();
+            .unwrap();
 
         let wishes = if auth_user.id == user_id {
             cx.select_list(wishes::owner.eq(user_id))
-        } else if friends {
+        } else if let Some(_friendship) = &friendship {
             cx.select_list(
                 wishes::owner.eq(user_id).and(
                     wishes::access_level
@@ -319,7 +293,53 @@ pub mod services {
 
     pub fn foo(sess: Session) {
         let mut cx = sess.into_context();
-        let wishes: Vec<Wish> = cx.select_list(true).unwrap();
+        let auth_user = cx.auth_user();
+        let wishes: Vec<Wish> = cx.select_list(wishes::owner.eq(auth_user.id)).unwrap();
+    }
+
+    pub fn friends_wishes(sess: Session) {
+        let mut cx = sess.into_context();
+
+        let auth_user = cx.auth_user();
+        let Ok(friendships) = cx.select_list(friendships::user2.eq(auth_user.id)) else {
+            return;
+        };
+
+        let mut friend_ids = ValueSet::new();
+        for f in friendships {
+            friend_ids.insert(f.user1);
+        }
+
+        let Ok(wishes) = cx.select_list(
+            wishes::owner
+                .eq_any(friend_ids.clone())
+                .and(wishes::access_level.eq(PUBLIC)),
+        ) else {
+            return;
+        };
+
+        for w in wishes {
+            assert(friend_ids.contains(&w.owner));
+        }
+    }
+
+    pub fn browse(sess: Session, min_price: i32, max_price: i32) {
+        let mut cx = sess.into_context();
+
+        let auth_user = cx.auth_user();
+        let Ok(wishes) = cx.select_list(
+            wishes::owner
+                .ne(auth_user.id)
+                .and(wishes::price.between(min_price, max_price))
+                .and(wishes::access_level.eq(PUBLIC)),
+        ) else {
+            return;
+        };
+
+        for w in wishes {
+            assert(w.owner != auth_user.id);
+            assert(min_price <= w.price && w.price <= max_price);
+        }
     }
 
     #[rocket::put("/wish")]
@@ -346,6 +366,7 @@ fn main() {
         rocket::build()
             .mount("/", routes![services::user_show])
             .mount("/", routes![services::new_wish])
+            .mount("/", routes![services::delete_wish])
             .attach(Template::fairing())
             .launch(),
     );
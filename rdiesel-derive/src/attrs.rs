@@ -0,0 +1,106 @@
+use proc_macro2::TokenStream;
+use syn::{meta::ParseNestedMeta, Attribute, Ident, LitStr, Path, Token, Type};
+
+/// `#[rdiesel(..)]` attributes on the model struct.
+#[derive(Default)]
+pub struct ModelAttrs {
+    pub user: Option<Type>,
+    pub read: Option<TokenStream>,
+    pub insert: Option<TokenStream>,
+    pub delete: Option<TokenStream>,
+    pub invariant: Option<TokenStream>,
+    pub skip_columns: bool,
+}
+
+impl ModelAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<ModelAttrs> {
+        let mut result = ModelAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("rdiesel")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("user") {
+                    result.user = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("read") {
+                    result.read = Some(parse_policy(&meta)?);
+                } else if meta.path.is_ident("insert") {
+                    result.insert = Some(parse_policy(&meta)?);
+                } else if meta.path.is_ident("delete") {
+                    result.delete = Some(parse_policy(&meta)?);
+                } else if meta.path.is_ident("invariant") {
+                    result.invariant = Some(parse_policy(&meta)?);
+                } else if meta.path.is_ident("skip_columns") {
+                    result.skip_columns = true;
+                } else {
+                    return Err(meta.error("unknown rdiesel attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+/// `#[rdiesel(..)]` attributes on a field of the model struct.
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub index: Option<Ident>,
+    pub opaque: bool,
+    pub read: Option<TokenStream>,
+    pub update: Option<TokenStream>,
+    pub invariant: Option<TokenStream>,
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<FieldAttrs> {
+        let mut result = FieldAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("rdiesel")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("index") {
+                    result.index = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("opaque") {
+                    result.opaque = true;
+                } else if meta.path.is_ident("read") {
+                    result.read = Some(parse_policy(&meta)?);
+                } else if meta.path.is_ident("update") {
+                    result.update = Some(parse_policy(&meta)?);
+                } else if meta.path.is_ident("invariant") {
+                    result.invariant = Some(parse_policy(&meta)?);
+                } else {
+                    return Err(meta.error("unknown rdiesel attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+}
+
+/// Finds `key = value` in the `#[diesel(..)]` attributes, skipping every other diesel option.
+pub fn diesel_option<T: syn::parse::Parse>(
+    attrs: &[Attribute],
+    key: &str,
+) -> syn::Result<Option<T>> {
+    let mut result = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("diesel")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                result = Some(meta.value()?.parse()?);
+            } else if meta.input.peek(Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            } else if meta.input.peek(syn::token::Paren) {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                content.parse::<TokenStream>()?;
+            }
+            Ok(())
+        })?;
+    }
+    Ok(result)
+}
+
+pub fn table_name(attrs: &[Attribute]) -> syn::Result<Option<Path>> {
+    diesel_option(attrs, "table_name")
+}
+
+fn parse_policy(meta: &ParseNestedMeta) -> syn::Result<TokenStream> {
+    meta.value()?.parse::<LitStr>()?.parse()
+}
@@ -0,0 +1,171 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{BinOp, Expr, Member, UnOp};
+
+/// A policy translated to the `Expr` DSL, along with its type.
+pub struct Filter {
+    pub ty: TokenStream,
+    pub expr: TokenStream,
+}
+
+/// Translates a policy to a filter over the columns of `table` that selects the rows it allows.
+///
+/// Comparisons between columns (`row.field`) and values that do not depend on the row, e.g.,
+/// `user.id`, `val` or constants, are translated to the corresponding `Expr` methods, and so are `&&`,
+/// `||` and `!`. Subexpressions that do not depend on the row are evaluated in Rust. Anything
+/// else, such as uninterpreted functions over the row, is replaced by `false`, or by `true` under
+/// a negation, so the filter never admits a row that the policy does not. Values are converted to
+/// the type of the column they are compared with, e.g., string literals to `String`.
+pub fn translate(
+    policy: Option<&TokenStream>,
+    table: &impl ToTokens,
+    row: &impl ToTokens,
+) -> Filter {
+    let cx = Cx {
+        table: table.to_token_stream(),
+        row: row.to_token_stream(),
+    };
+    match policy.cloned().map(syn::parse2::<Expr>) {
+        Some(Ok(policy)) => cx.translate(&policy, true),
+        _ => Filter::constant(false),
+    }
+}
+
+struct Cx {
+    table: TokenStream,
+    row: TokenStream,
+}
+
+enum Operand<'a> {
+    Column(&'a Member),
+    Value(&'a Expr),
+}
+
+impl Cx {
+    fn translate(&self, expr: &Expr, positive: bool) -> Filter {
+        let row = &self.row;
+        if !mentions_row(expr) {
+            return Filter {
+                ty: quote!(bool),
+                expr: quote!({ let b: bool = #expr; b }),
+            };
+        }
+        match expr {
+            Expr::Paren(expr) => self.translate(&expr.expr, positive),
+            Expr::Group(expr) => self.translate(&expr.expr, positive),
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Not(_)) => {
+                let Filter { ty, expr } = self.translate(&unary.expr, !positive);
+                Filter {
+                    ty: quote!(::rdiesel::Not<#ty>),
+                    expr: quote!(::rdiesel::Expr::<#row, bool>::not(#expr)),
+                }
+            }
+            Expr::Binary(binary) => {
+                let (node, method) = match binary.op {
+                    BinOp::And(_) => (quote!(And), quote!(and)),
+                    BinOp::Or(_) => (quote!(Or), quote!(or)),
+                    _ => {
+                        return self
+                            .comparison(binary.op, &binary.left, &binary.right)
+                            .unwrap_or_else(|| Filter::constant(!positive))
+                    }
+                };
+                let lhs = self.translate(&binary.left, positive);
+                let rhs = self.translate(&binary.right, positive);
+                let (lhs_ty, rhs_ty) = (&lhs.ty, &rhs.ty);
+                let (lhs, rhs) = (&lhs.expr, &rhs.expr);
+                Filter {
+                    ty: quote!(::rdiesel::#node<#lhs_ty, #rhs_ty>),
+                    expr: quote!(::rdiesel::Expr::<#row, bool>::#method(#lhs, #rhs)),
+                }
+            }
+            _ => Filter::constant(!positive),
+        }
+    }
+
+    fn comparison(&self, op: BinOp, lhs: &Expr, rhs: &Expr) -> Option<Filter> {
+        let (column, value, op) = match (operand(lhs)?, operand(rhs)?) {
+            (Operand::Column(column), value) => (column, value, op),
+            (value, Operand::Column(column)) => (column, value, mirror(op)?),
+            (Operand::Value(_), Operand::Value(_)) => return None,
+        };
+        let (node, method) = match op {
+            BinOp::Eq(_) => (quote!(Eq), quote!(eq)),
+            BinOp::Ne(_) => (quote!(Ne), quote!(ne)),
+            BinOp::Lt(_) => (quote!(Lt), quote!(lt)),
+            BinOp::Le(_) => (quote!(Le), quote!(le)),
+            BinOp::Gt(_) => (quote!(Gt), quote!(gt)),
+            BinOp::Ge(_) => (quote!(Ge), quote!(ge)),
+            _ => return None,
+        };
+        let Cx { table, row } = self;
+        let column = quote!(#table::#column);
+        let value_ty = quote!(<#column as ::rdiesel::runtime::ColumnValue<#row>>::Value);
+        let (rhs_ty, rhs) = match value {
+            Operand::Column(other) => (quote!(#table::#other), quote!(#table::#other)),
+            Operand::Value(value) => (
+                value_ty.clone(),
+                quote!({
+                    let val: #value_ty =
+                        ::core::convert::Into::into(::core::clone::Clone::clone(&(#value)));
+                    val
+                }),
+            ),
+        };
+        Some(Filter {
+            ty: quote!(::rdiesel::#node<#value_ty, #column, #rhs_ty>),
+            expr: quote!(::rdiesel::Expr::<#row, #value_ty>::#method(#column, #rhs)),
+        })
+    }
+}
+
+impl Filter {
+    fn constant(value: bool) -> Filter {
+        Filter {
+            ty: quote!(bool),
+            expr: quote!(#value),
+        }
+    }
+}
+
+fn operand(expr: &Expr) -> Option<Operand<'_>> {
+    match expr {
+        Expr::Paren(expr) => operand(&expr.expr),
+        Expr::Group(expr) => operand(&expr.expr),
+        Expr::Field(field) if is_row(&field.base) => Some(Operand::Column(&field.member)),
+        _ if !mentions_row(expr) => Some(Operand::Value(expr)),
+        _ => None,
+    }
+}
+
+/// The operator `op'` such that `a op b` iff `b op' a`.
+fn mirror(op: BinOp) -> Option<BinOp> {
+    Some(match op {
+        BinOp::Eq(_) | BinOp::Ne(_) => op,
+        BinOp::Lt(_) => BinOp::Gt(Default::default()),
+        BinOp::Le(_) => BinOp::Ge(Default::default()),
+        BinOp::Gt(_) => BinOp::Lt(Default::default()),
+        BinOp::Ge(_) => BinOp::Le(Default::default()),
+        _ => return None,
+    })
+}
+
+fn is_row(expr: &Expr) -> bool {
+    matches!(expr, Expr::Path(path) if path.path.is_ident("row"))
+}
+
+fn mentions_row(expr: &Expr) -> bool {
+    mentions(expr, "row")
+}
+
+/// Whether `name` occurs in `expr` as an identifier.
+pub fn mentions(expr: &Expr, name: &str) -> bool {
+    fn go(tokens: TokenStream, name: &str) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == name,
+            TokenTree::Group(group) => go(group.stream(), name),
+            _ => false,
+        })
+    }
+    go(expr.to_token_stream(), name)
+}
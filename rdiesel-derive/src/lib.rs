@@ -0,0 +1,86 @@
+//! Derive macros for `rdiesel`. These are re-exported by `rdiesel` and should be used from there.
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod attrs;
+mod filter;
+mod model;
+mod policy;
+mod rls;
+mod sort;
+
+/// Derives the `rdiesel` impls for a diesel model, i.e., a struct with a
+/// `#[diesel(table_name = ...)]` attribute.
+///
+/// For every field, an `Expr<Model, T>` impl is emitted for the corresponding column that maps it
+/// to the field's refinement index. Integer and `bool` fields are assumed to be indexed by their
+/// own name; other fields are treated as uninterpreted unless an index is given explicitly with
+/// `#[rdiesel(index = name)]`. `#[rdiesel(opaque)]` opts an integer or `bool` field out. The
+/// column also gets an `ExprRuntime<Model>` impl that reads the field, so that filters can be
+/// evaluated over values of the model, see `rdiesel::interpreter`.
+///
+/// Policies are only emitted when the struct is annotated with `#[rdiesel(user = User)]`. Each
+/// policy is a Flux expression over `user`, `row` and, for column updates and invariants, the
+/// assigned value `val`:
+///
+/// * `#[rdiesel(read = "..", insert = "..", delete = "..", invariant = "..")]` on the struct
+///   emit the `Row<User>` impl.
+/// * `#[rdiesel(read = "..", update = "..", invariant = "..")]` on a field emit the
+///   `Field<Model, User, T>` impl for its column.
+///
+/// Missing policies deny, except that a field whose column the struct's `invariant` does not
+/// mention gets the invariant `true`, as no assignment to it can break the row invariant.
+/// `#[rdiesel(skip_columns)]` on the struct only emits the `Row` impl,
+/// which is what insertable structs need. To keep all policies of a table in one place, leave out
+/// `user` and write them with [`policy!`] instead.
+#[proc_macro_derive(RdieselModel, attributes(rdiesel))]
+pub fn derive_rdiesel_model(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    model::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Declares the policies of one or more tables in one place.
+///
+/// ```ignore
+/// rdiesel::policy! {
+///     table schema::wishes(Wish) for User {
+///         read: user.id == row.owner || row.access_level == PUBLIC,
+///         insert(NewWish): user.id == row.owner,
+///         update(title: String, price: i32): user.id == row.owner,
+///         delete: user.id == row.owner,
+///         invariant: row.price >= 0,
+///         invariant(price: i32): val >= 0,
+///     }
+/// }
+/// ```
+///
+/// Each table is given by the path of its diesel module, its row type and the user type. A clause
+/// is a Flux expression over `user`, `row` and, for column updates and invariants, the assigned
+/// value `val`. The clauses expand as follows:
+///
+/// * `read`, `delete` and `invariant` emit the `Row<User>` impl of the row type.
+/// * `insert` adds to that impl or, when given an insertable type as in `insert(NewWish)`, emits
+///   the `Row<User>` impl of that type instead, which shares the table's `invariant`.
+/// * `read(..)`, `update(..)` and `invariant(..)` list the columns they apply to along with their
+///   Rust types, and emit one `Field<Row, User, T>` impl per column.
+///
+/// Missing clauses deny. In particular, a column that the table's `invariant` mentions as
+/// `row.column` cannot be updated without an `invariant(..)` clause of its own; other columns get
+/// the invariant `true`. As column invariants are checked against the old row, an update may
+/// assign at most one column that the table's `invariant` mentions. A column that no clause
+/// mentions has no `Field` impl and can thus be neither read with `select_columns` nor updated.
+/// `Changeset` needs no impls of its own, as it is implemented for every assignment and tuple of
+/// assignments to such columns.
+///
+/// With the `runtime-checks` feature of `rdiesel`, every clause is also compiled to a Rust
+/// predicate, see `rdiesel::runtime`. The same holds for the policies of `RdieselModel`.
+///
+/// The `read`, `insert`, `update` and `delete` clauses are also translated to PostgreSQL
+/// row-level security policies, see `rdiesel::rls`.
+#[proc_macro]
+pub fn policy(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as policy::Policies);
+    policy::expand(input).into()
+}
@@ -0,0 +1,129 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Ident};
+
+use crate::{
+    attrs::{self, FieldAttrs, ModelAttrs},
+    policy,
+    sort::{self, Sort},
+};
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "RdieselModel cannot be derived for generic structs",
+        ));
+    }
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "RdieselModel can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "RdieselModel can only be derived for structs with named fields",
+        ));
+    };
+    let model_attrs = ModelAttrs::parse(&input.attrs)?;
+    let model = &input.ident;
+
+    let Some(table) = attrs::table_name(&input.attrs)? else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "missing `#[diesel(table_name = ...)]` attribute",
+        ));
+    };
+
+    let mut output = TokenStream::new();
+    if let Some(user) = &model_attrs.user {
+        output.extend(policy::row_impl(
+            &table,
+            model,
+            user,
+            model_attrs.insert.as_ref(),
+            model_attrs.read.as_ref(),
+            model_attrs.delete.as_ref(),
+            model_attrs.invariant.as_ref(),
+        ));
+    }
+    if model_attrs.skip_columns {
+        return Ok(output);
+    }
+
+    for field in &fields.named {
+        let field_attrs = FieldAttrs::parse(&field.attrs)?;
+        let ident = field.ident.as_ref().unwrap();
+        let column_name = attrs::diesel_option::<Ident>(&field.attrs, "column_name")?
+            .unwrap_or_else(|| ident.clone());
+        let column = quote!(#table::#column_name);
+
+        // Nullable columns are compared by their non-null values, see `rdiesel::NullableExpr`.
+        let nullable = sort::option_inner(&field.ty);
+        let value_ty = nullable.unwrap_or(&field.ty);
+        let value_sort = Sort::of(value_ty);
+        let index = match (&field_attrs.index, field_attrs.opaque) {
+            (Some(index), _) => Some(index.clone()),
+            (None, false) if nullable.is_none() && value_sort.indexed_by_default() => {
+                Some(ident.clone())
+            }
+            _ => None,
+        };
+
+        output.extend(match &index {
+            Some(index) => quote! {
+                ::flux_rs::flux! {
+                    impl ::rdiesel::Expr<#model, #value_ty> for #column {
+                        reft eval(expr: Self, row: #model) -> #value_sort { row.#index }
+                    }
+                }
+            },
+            None => quote! {
+                impl ::rdiesel::Expr<#model, #value_ty> for #column {}
+            },
+        });
+        output.extend(quote! {
+            ::rdiesel::__if_runtime_checks! {
+                impl ::rdiesel::runtime::ColumnValue<#model> for #column {
+                    type Value = #value_ty;
+                }
+            }
+        });
+        let value = match nullable {
+            Some(_) => quote!(::core::clone::Clone::clone(&row.#ident)),
+            None => quote!(::core::option::Option::Some(::core::clone::Clone::clone(&row.#ident))),
+        };
+        output.extend(quote! {
+            impl ::rdiesel::interpreter::ExprRuntime<#model> for #column {
+                type Value = #value_ty;
+
+                fn eval(&self, row: &#model) -> ::core::option::Option<#value_ty> {
+                    #value
+                }
+            }
+        });
+        if nullable.is_some() {
+            output.extend(quote! {
+                impl ::rdiesel::NullableExpr<#model> for #column {}
+            });
+        }
+
+        if let Some(user) = &model_attrs.user {
+            let names = [Some(ident), index.as_ref()];
+            let names: Vec<&Ident> = names.into_iter().flatten().collect();
+            let policies = policy::FieldPolicies::new(
+                field_attrs.read.as_ref(),
+                field_attrs.update.as_ref(),
+                field_attrs.invariant.as_ref(),
+                model_attrs.invariant.as_ref(),
+                &names,
+            );
+            output.extend(policy::field_impl(
+                &table, &column, model, user, &field.ty, &policies,
+            ));
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,553 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Ident, Path, Token, Type,
+};
+
+use crate::{filter, rls, sort::Sort};
+
+mod kw {
+    syn::custom_keyword!(table);
+    syn::custom_keyword!(read);
+    syn::custom_keyword!(insert);
+    syn::custom_keyword!(update);
+    syn::custom_keyword!(delete);
+    syn::custom_keyword!(invariant);
+}
+
+/// The input of `policy!`: one or more table declarations.
+pub struct Policies {
+    tables: Vec<Table>,
+}
+
+/// `table path::to::table(Row) for User { clauses }`
+struct Table {
+    path: Path,
+    row: Type,
+    user: Type,
+    read: Option<TokenStream>,
+    insert: Option<(Option<Type>, TokenStream)>,
+    delete: Option<TokenStream>,
+    invariant: Option<TokenStream>,
+    columns: Vec<Column>,
+}
+
+/// The policies of a single column, collected from every clause that mentions it.
+struct Column {
+    name: Ident,
+    ty: Type,
+    read: Option<TokenStream>,
+    update: Option<TokenStream>,
+    invariant: Option<TokenStream>,
+}
+
+impl Parse for Policies {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut tables = vec![];
+        while !input.is_empty() {
+            tables.push(input.parse()?);
+        }
+        Ok(Policies { tables })
+    }
+}
+
+impl Parse for Table {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        input.parse::<kw::table>()?;
+        let path = input.parse()?;
+        let row;
+        parenthesized!(row in input);
+        let row = row.parse()?;
+        input.parse::<Token![for]>()?;
+        let user = input.parse()?;
+
+        let body;
+        syn::braced!(body in input);
+        let mut table = Table {
+            path,
+            row,
+            user,
+            read: None,
+            insert: None,
+            delete: None,
+            invariant: None,
+            columns: vec![],
+        };
+        while !body.is_empty() {
+            table.parse_clause(&body)?;
+            if !body.is_empty() {
+                body.parse::<Token![,]>()?;
+            }
+        }
+        Ok(table)
+    }
+}
+
+impl Table {
+    fn parse_clause(&mut self, input: ParseStream) -> syn::Result<()> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(kw::read) {
+            let kw = input.parse::<kw::read>()?;
+            if input.peek(syn::token::Paren) {
+                let columns = parse_columns(input)?;
+                let expr = parse_expr(input)?;
+                self.set_columns(columns, expr, |column| &mut column.read)
+            } else {
+                let expr = parse_expr(input)?;
+                set_once(&mut self.read, expr, kw.span, "read")
+            }
+        } else if lookahead.peek(kw::insert) {
+            let kw = input.parse::<kw::insert>()?;
+            let ty = if input.peek(syn::token::Paren) {
+                let content;
+                parenthesized!(content in input);
+                Some(content.parse()?)
+            } else {
+                None
+            };
+            let expr = parse_expr(input)?;
+            set_once(&mut self.insert, (ty, expr), kw.span, "insert")
+        } else if lookahead.peek(kw::update) {
+            input.parse::<kw::update>()?;
+            let columns = parse_columns(input)?;
+            let expr = parse_expr(input)?;
+            self.set_columns(columns, expr, |column| &mut column.update)
+        } else if lookahead.peek(kw::delete) {
+            let kw = input.parse::<kw::delete>()?;
+            let expr = parse_expr(input)?;
+            set_once(&mut self.delete, expr, kw.span, "delete")
+        } else if lookahead.peek(kw::invariant) {
+            let kw = input.parse::<kw::invariant>()?;
+            if input.peek(syn::token::Paren) {
+                let columns = parse_columns(input)?;
+                let expr = parse_expr(input)?;
+                self.set_columns(columns, expr, |column| &mut column.invariant)
+            } else {
+                let expr = parse_expr(input)?;
+                set_once(&mut self.invariant, expr, kw.span, "invariant")
+            }
+        } else {
+            Err(lookahead.error())
+        }
+    }
+
+    fn set_columns(
+        &mut self,
+        columns: Vec<(Ident, Type)>,
+        expr: TokenStream,
+        clause: impl Fn(&mut Column) -> &mut Option<TokenStream>,
+    ) -> syn::Result<()> {
+        for (name, ty) in columns {
+            let column = match self
+                .columns
+                .iter_mut()
+                .position(|column| column.name == name)
+            {
+                Some(i) => &mut self.columns[i],
+                None => {
+                    self.columns.push(Column {
+                        name: name.clone(),
+                        ty: ty.clone(),
+                        read: None,
+                        update: None,
+                        invariant: None,
+                    });
+                    self.columns.last_mut().unwrap()
+                }
+            };
+            if column.ty != ty {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    format!("column `{name}` was declared with a different type"),
+                ));
+            }
+            let span = name.span();
+            set_once(clause(column), expr.clone(), span, &format!("`{name}`"))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_columns(input: ParseStream) -> syn::Result<Vec<(Ident, Type)>> {
+    let content;
+    parenthesized!(content in input);
+    let columns =
+        Punctuated::<(Ident, Type), Token![,]>::parse_terminated_with(&content, |input| {
+            let name = input.parse()?;
+            input.parse::<Token![:]>()?;
+            Ok((name, input.parse()?))
+        })?;
+    Ok(columns.into_iter().collect())
+}
+
+/// Parses `: expr` where `expr` is every token up to the next top-level comma.
+fn parse_expr(input: ParseStream) -> syn::Result<TokenStream> {
+    input.parse::<Token![:]>()?;
+    let mut expr = TokenStream::new();
+    while !input.is_empty() && !input.peek(Token![,]) {
+        expr.extend([input.parse::<TokenTree>()?]);
+    }
+    if expr.is_empty() {
+        return Err(input.error("expected a policy"));
+    }
+    Ok(expr)
+}
+
+fn set_once<T>(
+    slot: &mut Option<T>,
+    value: T,
+    span: proc_macro2::Span,
+    what: &str,
+) -> syn::Result<()> {
+    if slot.is_some() {
+        return Err(syn::Error::new(
+            span,
+            format!("duplicate policy for {what}"),
+        ));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+pub fn expand(policies: Policies) -> TokenStream {
+    policies.tables.iter().map(Table::expand).collect()
+}
+
+impl Table {
+    fn expand(&self) -> TokenStream {
+        let Table {
+            path, row, user, ..
+        } = self;
+        let mut output = TokenStream::new();
+
+        let (insert_ty, insert) = match &self.insert {
+            Some((ty, expr)) => (ty.as_ref(), Some(expr)),
+            None => (None, None),
+        };
+        // When inserts go through a separate type, the row type itself cannot be inserted.
+        let row_insert = if insert_ty.is_some() { None } else { insert };
+        output.extend(row_impl(
+            path,
+            row,
+            user,
+            row_insert,
+            self.read.as_ref(),
+            self.delete.as_ref(),
+            self.invariant.as_ref(),
+        ));
+        if let Some(insert_ty) = insert_ty {
+            output.extend(row_impl(
+                path,
+                insert_ty,
+                user,
+                insert,
+                None,
+                None,
+                self.invariant.as_ref(),
+            ));
+        }
+
+        for column in &self.columns {
+            let Column { name, ty, .. } = column;
+            let policies = FieldPolicies::new(
+                column.read.as_ref(),
+                column.update.as_ref(),
+                column.invariant.as_ref(),
+                self.invariant.as_ref(),
+                &[name],
+            );
+            output.extend(field_impl(
+                path,
+                &quote!(#path::#name),
+                row,
+                user,
+                ty,
+                &policies,
+            ));
+        }
+        output.extend(self.row_level_security(insert));
+        output
+    }
+
+    /// The `RowLevelSecurity` impl of the table.
+    fn row_level_security(&self, insert: Option<&TokenStream>) -> TokenStream {
+        let path = &self.path;
+        let translate = |policy: Option<&TokenStream>| match policy {
+            Some(policy) => {
+                let sql = rls::translate(policy, path);
+                quote!(::core::option::Option::Some(#sql))
+            }
+            None => quote!(::core::option::Option::None),
+        };
+        // A row can be read if it or any of its columns can, and updated if any of its columns
+        // can.
+        let columns = &self.columns;
+        let select = any(self
+            .read
+            .iter()
+            .chain(columns.iter().filter_map(|column| column.read.as_ref())));
+        let update = any(columns.iter().filter_map(|column| column.update.as_ref()));
+        let select = translate(select.as_ref());
+        let insert = translate(insert);
+        let update = translate(update.as_ref());
+        let delete = translate(self.delete.as_ref());
+        quote! {
+            #[::flux_rs::ignore]
+            const _: () = {
+                impl ::rdiesel::rls::RowLevelSecurity for #path::table {
+                    #[allow(unused_variables)]
+                    fn row_level_security(
+                        user: &::rdiesel::rls::UserSettings,
+                    ) -> ::rdiesel::rls::TablePolicies {
+                        ::rdiesel::rls::TablePolicies {
+                            table: ::rdiesel::rls::table_name(&#path::table),
+                            select: #select,
+                            insert: #insert,
+                            update: #update,
+                            delete: #delete,
+                        }
+                    }
+                }
+            };
+        }
+    }
+}
+
+/// The disjunction of `policies` without duplicates, if there are any.
+fn any<'a>(policies: impl Iterator<Item = &'a TokenStream>) -> Option<TokenStream> {
+    let mut disjuncts: Vec<String> = vec![];
+    for policy in policies {
+        let policy = format!("({policy})");
+        if !disjuncts.contains(&policy) {
+            disjuncts.push(policy);
+        }
+    }
+    (!disjuncts.is_empty()).then(|| disjuncts.join(" || ").parse().unwrap())
+}
+
+/// The `Row<User>` impl of `row` and, with runtime checks, its `RowPolicy<User>`,
+/// `ReadFilter<User>` and `DeleteFilter<User>` impls. Missing policies deny.
+pub fn row_impl(
+    table: &impl ToTokens,
+    row: &impl ToTokens,
+    user: &Type,
+    insert: Option<&TokenStream>,
+    read: Option<&TokenStream>,
+    delete: Option<&TokenStream>,
+    invariant: Option<&TokenStream>,
+) -> TokenStream {
+    let read_filter = filter::translate(read, table, row);
+    let delete_filter = filter::translate(delete, table, row);
+    let (read_filter_ty, read_filter) = (&read_filter.ty, &read_filter.expr);
+    let (delete_filter_ty, delete_filter) = (&delete_filter.ty, &delete_filter.expr);
+    let insert = insert.cloned().unwrap_or_else(|| quote!(false));
+    let (read, runtime_read) = read
+        .map(|read| {
+            (
+                quote! { reft allow_read(user: #user, row: #row) -> bool { #read } },
+                quote! { fn allow_read(&self, user: &#user) -> bool { let row = self; #read } },
+            )
+        })
+        .unzip();
+    let (delete, runtime_delete) = delete
+        .map(|delete| {
+            (
+                quote! { reft allow_delete(user: #user, row: #row) -> bool { #delete } },
+                quote! { fn allow_delete(&self, user: &#user) -> bool { let row = self; #delete } },
+            )
+        })
+        .unzip();
+    let (invariant, runtime_invariant) = invariant
+        .map(|invariant| {
+            (
+                quote! { reft invariant(row: #row) -> bool { #invariant } },
+                quote! { fn invariant(&self) -> bool { let row = self; #invariant } },
+            )
+        })
+        .unzip();
+    quote! {
+        ::flux_rs::flux! {
+            impl ::rdiesel::Row<#user> for #row {
+                reft allow_insert(user: #user, row: #row) -> bool { #insert }
+
+                #read
+
+                #delete
+
+                #invariant
+            }
+        }
+
+        ::rdiesel::__if_runtime_checks! {
+            #[allow(unused_variables)]
+            impl ::rdiesel::runtime::RowPolicy<#user> for #row {
+                fn allow_insert(&self, user: &#user) -> bool { let row = self; #insert }
+
+                #runtime_read
+
+                #runtime_delete
+
+                #runtime_invariant
+            }
+
+            #[allow(unused_variables)]
+            impl ::rdiesel::runtime::ReadFilter<#user> for #row {
+                type Filter = #read_filter_ty;
+
+                fn read_filter(user: &#user) -> Self::Filter {
+                    #read_filter
+                }
+            }
+
+            #[allow(unused_variables)]
+            impl ::rdiesel::runtime::DeleteFilter<#user> for #row {
+                type Filter = #delete_filter_ty;
+
+                fn delete_filter(user: &#user) -> Self::Filter {
+                    #delete_filter
+                }
+            }
+        }
+    }
+}
+
+/// The policies of a column, as declared for it and derived from the row invariant.
+pub struct FieldPolicies<'a> {
+    pub read: Option<&'a TokenStream>,
+    pub update: Option<&'a TokenStream>,
+    pub invariant: Option<TokenStream>,
+    pub in_row_invariant: bool,
+}
+
+impl<'a> FieldPolicies<'a> {
+    /// The policies of a column with the given `read`, `update` and `invariant` policies in a
+    /// row with the invariant `row_invariant`. A column that the row invariant does not mention
+    /// cannot break it and gets the invariant `true`; otherwise a missing policy denies every
+    /// assignment. `names` are the names by which `row` refers to the column.
+    pub fn new(
+        read: Option<&'a TokenStream>,
+        update: Option<&'a TokenStream>,
+        invariant: Option<&'a TokenStream>,
+        row_invariant: Option<&TokenStream>,
+        names: &[&Ident],
+    ) -> Self {
+        fn mentions(tokens: TokenStream, names: &[&Ident]) -> bool {
+            let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+            tokens.iter().enumerate().any(|(i, token)| match token {
+                TokenTree::Group(group) => mentions(group.stream(), names),
+                TokenTree::Ident(row) if row == "row" => matches!(
+                    &tokens[i + 1..],
+                    [TokenTree::Punct(dot), TokenTree::Ident(field), ..]
+                        if dot.as_char() == '.' && names.contains(&field)
+                ),
+                _ => false,
+            })
+        }
+        let in_row_invariant =
+            row_invariant.is_some_and(|row_invariant| mentions(row_invariant.clone(), names));
+        let invariant = match invariant {
+            Some(invariant) => Some(invariant.clone()),
+            None if in_row_invariant => None,
+            None => Some(quote!(true)),
+        };
+        FieldPolicies {
+            read,
+            update,
+            invariant,
+            in_row_invariant,
+        }
+    }
+}
+
+/// The `Field<Row, User, T>` impl of `column` and, with runtime checks, its
+/// `FieldPolicy<Row, User, T>` and `UpdateFilter<Row, User, T>` impls. Missing policies deny.
+pub fn field_impl(
+    table: &impl ToTokens,
+    column: &TokenStream,
+    row: &impl ToTokens,
+    user: &Type,
+    ty: &Type,
+    policies: &FieldPolicies,
+) -> TokenStream {
+    let FieldPolicies {
+        read,
+        update,
+        invariant,
+        in_row_invariant,
+    } = policies;
+    let sort = Sort::of(ty);
+    let update = update.cloned().unwrap_or_else(|| quote!(false));
+    let update_filter = invariant
+        .as_ref()
+        .map(|invariant| quote!((#update) && (#invariant)));
+    let filter::Filter {
+        ty: update_filter_ty,
+        expr: update_filter,
+    } = filter::translate(update_filter.as_ref(), table, row);
+    let (read, runtime_read) = read
+        .map(|read| {
+            (
+                quote! { reft allow_read(user: #user, row: #row) -> bool { #read } },
+                quote! { fn allow_read(user: &#user, row: &#row) -> bool { #read } },
+            )
+        })
+        .unzip();
+    let (invariant, runtime_invariant) = invariant
+        .as_ref()
+        .map(|invariant| {
+            (
+                quote! { reft invariant(row: #row, val: #sort) -> bool { #invariant } },
+                quote! {
+                    fn invariant(row: &#row, val: &#ty) -> bool {
+                        let val = ::core::clone::Clone::clone(val);
+                        #invariant
+                    }
+                },
+            )
+        })
+        .unzip();
+    quote! {
+        ::flux_rs::flux! {
+            impl ::rdiesel::Field<#row, #user, #ty> for #column {
+                reft allow_update(user: #user, row: #row, val: #sort) -> bool { #update }
+
+                #read
+
+                #invariant
+
+                reft in_row_invariant(row: #row, val: #sort) -> bool { #in_row_invariant }
+            }
+        }
+
+        ::rdiesel::__if_runtime_checks! {
+            #[allow(unused_variables)]
+            impl ::rdiesel::runtime::FieldPolicy<#row, #user, #ty> for #column {
+                fn allow_update(user: &#user, row: &#row, val: &#ty) -> bool {
+                    let val = ::core::clone::Clone::clone(val);
+                    #update
+                }
+
+                #runtime_read
+
+                #runtime_invariant
+
+                fn in_row_invariant() -> bool {
+                    #in_row_invariant
+                }
+            }
+
+            #[allow(unused_variables)]
+            impl ::rdiesel::runtime::UpdateFilter<#row, #user, #ty> for #column {
+                type Filter = #update_filter_ty;
+
+                fn update_filter(user: &#user, val: &#ty) -> Self::Filter {
+                    let val = ::core::clone::Clone::clone(val);
+                    #update_filter
+                }
+            }
+        }
+    }
+}
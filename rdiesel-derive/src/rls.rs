@@ -0,0 +1,184 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{BinOp, Expr, Lit, UnOp};
+
+use crate::filter::mentions;
+
+/// A policy translated to a SQL expression, as the arguments of a `format!` call.
+struct Sql {
+    fmt: String,
+    args: Vec<TokenStream>,
+}
+
+/// Translates a policy to a SQL expression for a PostgreSQL row-level security policy on
+/// `table`. The result is an expression of type `String` in which `user` is bound to a
+/// `&rdiesel::rls::UserSettings`.
+///
+/// Columns (`row.field`) are translated to their names, fields of the user to their settings,
+/// function calls to calls of SQL functions of the same name and Rust values that depend on
+/// neither to SQL literals. Anything else is replaced by `FALSE`, or by `TRUE` under a negation,
+/// so the resulting policy never admits a row that the original one does not.
+pub fn translate(policy: &TokenStream, table: &impl ToTokens) -> TokenStream {
+    let sql = match syn::parse2::<Expr>(policy.clone()) {
+        Ok(policy) => Cx { table }.translate(&policy, true),
+        Err(_) => Sql::constant(false),
+    };
+    let Sql { fmt, args } = sql;
+    quote!(::std::format!(#fmt #(, #args)*))
+}
+
+struct Cx<'a, T> {
+    table: &'a T,
+}
+
+impl<T: ToTokens> Cx<'_, T> {
+    fn translate(&self, expr: &Expr, positive: bool) -> Sql {
+        if let Some(sql) = self.term(expr) {
+            return sql;
+        }
+        match expr {
+            Expr::Paren(expr) => self.translate(&expr.expr, positive),
+            Expr::Group(expr) => self.translate(&expr.expr, positive),
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Not(_)) => {
+                let inner = self.translate(&unary.expr, !positive);
+                Sql::wrap("NOT (", inner, ")")
+            }
+            Expr::Binary(binary) if matches!(binary.op, BinOp::And(_) | BinOp::Or(_)) => {
+                let op = if matches!(binary.op, BinOp::And(_)) {
+                    " AND "
+                } else {
+                    " OR "
+                };
+                let lhs = self.translate(&binary.left, positive);
+                let rhs = self.translate(&binary.right, positive);
+                Sql::binary(lhs, op, rhs)
+            }
+            _ => Sql::constant(!positive),
+        }
+    }
+
+    /// Translates an expression that is not a boolean connective, if it can be translated
+    /// exactly.
+    fn term(&self, expr: &Expr) -> Option<Sql> {
+        let table = self.table;
+        match expr {
+            Expr::Paren(expr) => Some(Sql::wrap("(", self.term(&expr.expr)?, ")")),
+            Expr::Group(expr) => self.term(&expr.expr),
+            Expr::Lit(lit) => literal(&lit.lit),
+            Expr::Field(field) if is_ident(&field.base, "row") => {
+                let column = &field.member;
+                Some(Sql::arg(
+                    "\"{}\"",
+                    quote!(<#table::#column as ::diesel::Column>::NAME),
+                ))
+            }
+            Expr::Field(field) if is_ident(&field.base, "user") => {
+                let name = field.member.to_token_stream().to_string();
+                Some(Sql::arg("{}", quote!(user.field(#name))))
+            }
+            Expr::Binary(binary) => {
+                let op = match binary.op {
+                    BinOp::Eq(_) => " = ",
+                    BinOp::Ne(_) => " <> ",
+                    BinOp::Lt(_) => " < ",
+                    BinOp::Le(_) => " <= ",
+                    BinOp::Gt(_) => " > ",
+                    BinOp::Ge(_) => " >= ",
+                    BinOp::Add(_) => " + ",
+                    BinOp::Sub(_) => " - ",
+                    BinOp::Mul(_) => " * ",
+                    BinOp::Div(_) => " / ",
+                    BinOp::Rem(_) => " % ",
+                    _ => return None,
+                };
+                Some(Sql::binary(
+                    self.term(&binary.left)?,
+                    op,
+                    self.term(&binary.right)?,
+                ))
+            }
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => {
+                Some(Sql::wrap("-", self.term(&unary.expr)?, ""))
+            }
+            Expr::Call(call) => {
+                let Expr::Path(func) = &*call.func else {
+                    return None;
+                };
+                let name = func.path.get_ident()?.to_string();
+                let mut sql = Sql::text(&format!("{name}("));
+                for (i, arg) in call.args.iter().enumerate() {
+                    if i > 0 {
+                        sql.push(Sql::text(", "));
+                    }
+                    sql.push(self.term(arg)?);
+                }
+                sql.push(Sql::text(")"));
+                Some(sql)
+            }
+            _ if ["row", "user", "val"]
+                .iter()
+                .all(|name| !mentions(expr, name)) =>
+            {
+                Some(Sql::arg(
+                    "{}",
+                    quote!(::rdiesel::rls::SqlLiteral::to_sql_literal(&(#expr))),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Sql {
+    fn text(text: &str) -> Sql {
+        Sql {
+            fmt: text.replace('{', "{{").replace('}', "}}"),
+            args: vec![],
+        }
+    }
+
+    fn arg(fmt: &str, arg: TokenStream) -> Sql {
+        Sql {
+            fmt: fmt.to_string(),
+            args: vec![arg],
+        }
+    }
+
+    fn constant(value: bool) -> Sql {
+        Sql::text(if value { "TRUE" } else { "FALSE" })
+    }
+
+    fn push(&mut self, other: Sql) {
+        self.fmt.push_str(&other.fmt);
+        self.args.extend(other.args);
+    }
+
+    fn wrap(open: &str, inner: Sql, close: &str) -> Sql {
+        let mut sql = Sql::text(open);
+        sql.push(inner);
+        sql.push(Sql::text(close));
+        sql
+    }
+
+    fn binary(lhs: Sql, op: &str, rhs: Sql) -> Sql {
+        let mut sql = Sql::text("(");
+        sql.push(lhs);
+        sql.push(Sql::text(op));
+        sql.push(rhs);
+        sql.push(Sql::text(")"));
+        sql
+    }
+}
+
+fn literal(lit: &Lit) -> Option<Sql> {
+    match lit {
+        Lit::Bool(lit) => Some(Sql::constant(lit.value)),
+        Lit::Int(lit) => Some(Sql::text(lit.base10_digits())),
+        Lit::Str(lit) => Some(Sql::text(&format!("'{}'", lit.value().replace('\'', "''")))),
+        _ => None,
+    }
+}
+
+fn is_ident(expr: &Expr, name: &str) -> bool {
+    matches!(expr, Expr::Path(path) if path.path.is_ident(name))
+}
@@ -0,0 +1,69 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{GenericArgument, PathArguments, Type};
+
+/// The Flux sort a Rust type is refined by.
+pub enum Sort {
+    Int,
+    Bool,
+    Str,
+    /// Values of any other type are compared as an uninterpreted sort named after the type.
+    Other(Box<Type>),
+}
+
+impl Sort {
+    pub fn of(ty: &Type) -> Sort {
+        match last_ident(ty).as_deref() {
+            Some(
+                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+                | "u128" | "usize",
+            ) => Sort::Int,
+            Some("bool") => Sort::Bool,
+            Some("String") => Sort::Str,
+            _ => Sort::Other(Box::new(ty.clone())),
+        }
+    }
+
+    /// Whether fields of this sort are indexed by their own name unless told otherwise.
+    pub fn indexed_by_default(&self) -> bool {
+        matches!(self, Sort::Int | Sort::Bool)
+    }
+}
+
+impl ToTokens for Sort {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Sort::Int => tokens.extend(quote!(int)),
+            Sort::Bool => tokens.extend(quote!(bool)),
+            Sort::Str => tokens.extend(quote!(str)),
+            Sort::Other(ty) => ty.to_tokens(tokens),
+        }
+    }
+}
+
+/// Returns `T` if `ty` is `Option<T>`. Those are the fields of nullable columns.
+pub fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(inner) => Some(inner),
+        _ => None,
+    }
+}
+
+fn last_ident(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            Some(path.path.segments.last()?.ident.to_string())
+        }
+        _ => None,
+    }
+}
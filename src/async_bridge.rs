@@ -0,0 +1,123 @@
+//! The counterpart of [`bridge`](crate::bridge) for [`diesel_async::AsyncConnection`]s. Queries
+//! are built by the same [`SelectQuery`], [`UpdateQuery`], [`InsertQuery`] and [`DeleteQuery`]
+//! and executed with [`diesel_async::RunQueryDsl`].
+// Whether the futures are `Send` depends on the connection and the query, which is only known
+// where the traits are used with concrete types.
+#![allow(async_fn_in_trait)]
+
+use diesel::{
+    dsl::Limit,
+    query_dsl::methods::{LimitDsl, SelectDsl},
+    Expression, OptionalExtension, QueryResult,
+};
+use diesel_async::{
+    methods::{ExecuteDsl, LoadQuery},
+    AsyncConnection, RunQueryDsl,
+};
+
+use crate::bridge::{
+    Delete, DeleteQuery, InsertInto, InsertQuery, Select, SelectQuery, Update, UpdateQuery,
+};
+
+pub trait SelectList<Conn, Q>: Sized {
+    async fn select_list(conn: &mut Conn, q: Q) -> QueryResult<Vec<Self>>;
+}
+
+pub trait SelectFirst<Conn, Q>: Sized {
+    async fn select_first(conn: &mut Conn, q: Q) -> QueryResult<Option<Self>>;
+}
+
+pub trait SelectColumns<Conn, Q, S, O> {
+    async fn select_columns(conn: &mut Conn, q: Q, columns: S) -> QueryResult<Vec<O>>;
+}
+
+pub trait UpdateWhere<Conn, Q, C> {
+    async fn update_where(conn: &mut Conn, q: Q, v: C) -> QueryResult<usize>;
+}
+
+pub trait Insert<Conn> {
+    async fn insert(conn: &mut Conn, v: Self) -> QueryResult<usize>;
+}
+
+pub trait DeleteWhere<Conn, Q> {
+    async fn delete_where(conn: &mut Conn, q: Q) -> QueryResult<usize>;
+}
+
+#[flux_rs::ignore]
+const _: () = {
+    impl<Conn, R, Q> SelectList<Conn, Q> for R
+    where
+        R: SelectQuery<Q> + Send,
+        Conn: AsyncConnection,
+        Select<R, Q>: LoadQuery<'static, Conn, R> + 'static,
+    {
+        async fn select_list(conn: &mut Conn, q: Q) -> QueryResult<Vec<R>> {
+            R::select_query(q).load(conn).await
+        }
+    }
+
+    impl<Conn, R, Q> SelectFirst<Conn, Q> for R
+    where
+        R: SelectQuery<Q> + Send,
+        Conn: AsyncConnection,
+        Select<R, Q>: LimitDsl,
+        Limit<Select<R, Q>>: LoadQuery<'static, Conn, R> + 'static,
+    {
+        async fn select_first(conn: &mut Conn, q: Q) -> QueryResult<Option<R>> {
+            R::select_query(q)
+                .limit(1)
+                .get_result(conn)
+                .await
+                .optional()
+        }
+    }
+
+    impl<Conn, R, Q, S, O> SelectColumns<Conn, Q, S, O> for R
+    where
+        R: SelectQuery<Q>,
+        S: Expression,
+        O: Send,
+        Conn: AsyncConnection,
+        Select<R, Q>: SelectDsl<S>,
+        <Select<R, Q> as SelectDsl<S>>::Output: LoadQuery<'static, Conn, O> + 'static,
+    {
+        async fn select_columns(conn: &mut Conn, q: Q, columns: S) -> QueryResult<Vec<O>> {
+            SelectDsl::select(R::select_query(q), columns)
+                .load(conn)
+                .await
+        }
+    }
+
+    impl<Conn, R, Q, C> UpdateWhere<Conn, Q, C> for R
+    where
+        R: UpdateQuery<Q, C>,
+        Conn: AsyncConnection + Send,
+        Update<R, Q, C>: ExecuteDsl<Conn>,
+    {
+        async fn update_where(conn: &mut Conn, q: Q, v: C) -> QueryResult<usize> {
+            ExecuteDsl::execute(R::update_query(q, v), conn).await
+        }
+    }
+
+    impl<Conn, R> Insert<Conn> for R
+    where
+        R: InsertQuery,
+        Conn: AsyncConnection + Send,
+        InsertInto<R>: ExecuteDsl<Conn>,
+    {
+        async fn insert(conn: &mut Conn, v: Self) -> QueryResult<usize> {
+            ExecuteDsl::execute(R::insert_query(v), conn).await
+        }
+    }
+
+    impl<Conn, R, Q> DeleteWhere<Conn, Q> for R
+    where
+        R: DeleteQuery<Q>,
+        Conn: AsyncConnection + Send,
+        Delete<R, Q>: ExecuteDsl<Conn>,
+    {
+        async fn delete_where(conn: &mut Conn, q: Q) -> QueryResult<usize> {
+            ExecuteDsl::execute(R::delete_query(q), conn).await
+        }
+    }
+};
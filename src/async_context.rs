@@ -0,0 +1,173 @@
+//! [`Context`](crate::Context) over a [`diesel_async::AsyncConnection`], enabled by the `async`
+//! feature. The methods of [`AsyncContext`] have the same refinements as their blocking
+//! counterparts and are checked the same way.
+//!
+//! Since `Drop` cannot await, an [`AsyncContext`] does not run [`AsyncContextImpl::on_exit`]
+//! when it is dropped. Call [`AsyncContext::close`] instead.
+// Whether the futures are `Send` depends on the connection, which is only known where the trait
+// is implemented.
+#![allow(async_fn_in_trait)]
+use diesel::QueryResult;
+use diesel_async::{scoped_futures::ScopedBoxFuture, AsyncConnection, TransactionManager as _};
+use flux_rs::*;
+
+use crate::{async_guard as guard, Changeset, Expr, NoKvar, Projection, Row};
+
+/// The counterpart of [`ContextImpl`](crate::ContextImpl) for [`AsyncContext`].
+pub trait AsyncContextImpl {
+    type User;
+    type Conn;
+
+    fn auth_user(&self) -> Self::User;
+
+    fn conn(self: &mut Self) -> &mut Self::Conn;
+
+    /// Runs when an [`AsyncContext`] is created, before any query, see
+    /// [`ContextImpl::on_enter`](crate::ContextImpl::on_enter).
+    async fn on_enter(&mut self) -> QueryResult<()> {
+        Ok(())
+    }
+
+    /// Runs when an [`AsyncContext`] is closed with [`AsyncContext::close`], or when
+    /// [`AsyncContextImpl::on_enter`] fails, see
+    /// [`ContextImpl::on_exit`](crate::ContextImpl::on_exit).
+    async fn on_exit(&mut self) -> QueryResult<()> {
+        Ok(())
+    }
+}
+
+flux!(
+
+#[opaque]
+pub struct AsyncContext<T, U>[user: U] {
+    _u: std::marker::PhantomData<U>,
+    inner: T,
+}
+
+#[trusted]
+#[generics(U as base)]
+impl<T, U> AsyncContext<T, U>
+where
+    T: AsyncContextImpl<User = U>,
+    U: NoKvar,
+{
+    /// Creates a context for the user of `inner` and runs [`AsyncContextImpl::on_enter`]. If it
+    /// fails, [`AsyncContextImpl::on_exit`] runs as well, to undo whatever `on_enter` did before
+    /// failing.
+    pub async fn new(mut inner: T) -> QueryResult<Self> {
+        if let Err(err) = inner.on_enter().await {
+            let _ = inner.on_exit().await;
+            return Err(err);
+        }
+        Ok(Self {
+            _u: std::marker::PhantomData,
+            inner,
+        })
+    }
+
+    /// Runs [`AsyncContextImpl::on_exit`] and drops the context.
+    pub async fn close(mut self) -> QueryResult<()> {
+        self.inner.on_exit().await
+    }
+
+    pub fn auth_user(self: &Self[@cx]) -> U[cx.user] {
+        self.inner.auth_user()
+    }
+
+    pub async fn select_list<R as base, Q as base>(
+        self: &mut Self[@cx],
+        q: Q,
+    ) -> QueryResult<Vec<R{row: <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::allow_read(cx.user, row) && <R as Row<U>>::invariant(row)}>>
+    where
+        Q: Expr<R, bool>,
+        R: guard::SelectList<T::Conn, Q, U> + Row<U>,
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <R as Row<U>>::allow_read(cx.user, row)
+    {
+        let user = self.inner.auth_user();
+        R::select_list(self.inner.conn(), &user, q).await
+    }
+
+    pub async fn select_first<R as base, Q as base>(
+        self: &mut Self[@cx],
+        q: Q,
+    ) -> QueryResult<Option<R{row: <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::allow_read(cx.user, row) && <R as Row<U>>::invariant(row)}>>
+    where
+        Q: Expr<R, bool>,
+        R: guard::SelectFirst<T::Conn, Q, U> + Row<U>,
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <R as Row<U>>::allow_read(cx.user, row)
+    {
+        let user = self.inner.auth_user();
+        R::select_first(self.inner.conn(), &user, q).await
+    }
+
+    pub async fn select_columns<R as base, Q as base, S, V, O>(
+        self: &mut Self[@cx],
+        q: Q,
+        columns: S,
+    ) -> QueryResult<Vec<O>>
+    where
+        Q: Expr<R, bool>,
+        S: Projection<R, U, V>,
+        R: guard::SelectColumns<T::Conn, Q, S, O, U, V> + Row<U>,
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <S as Projection<R, U, V>>::allow_read(cx.user, row)
+    {
+        let user = self.inner.auth_user();
+        R::select_columns(self.inner.conn(), &user, q, columns).await
+    }
+
+    pub async fn update_where<R as base, Q as base, C as base>(self: &mut Self[@cx], q: Q, v: C) -> QueryResult<usize>
+    where
+        Q: Expr<R, bool>,
+        C: Changeset<R, U>,
+        R: guard::UpdateWhere<T::Conn, Q, C, U> + Row<U>
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) =>
+        <C as Changeset<R, U>>::allow_update(cx.user, row, v) && <C as Changeset<R, U>>::invariant(row, v)
+    {
+        let user = self.inner.auth_user();
+        R::update_where(self.inner.conn(), &user, q, v).await
+    }
+
+    pub async fn insert<R as base>(self: &mut Self[@cx], v: R{ <R as Row<U>>::allow_insert(cx.user, v) && <R as Row<U>>::invariant(v) }) -> QueryResult<usize>
+    where
+        R: guard::Insert<T::Conn, U> + Row<U>
+    {
+        let user = self.inner.auth_user();
+        R::insert(self.inner.conn(), &user, v).await
+    }
+
+    pub async fn delete_where<R as base, Q as base>(self: &mut Self[@cx], q: Q) -> QueryResult<usize>
+    where
+        Q: Expr<R, bool>,
+        R: guard::DeleteWhere<T::Conn, Q, U> + Row<U>
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <R as Row<U>>::allow_delete(cx.user, row)
+    {
+        let user = self.inner.auth_user();
+        R::delete_where(self.inner.conn(), &user, q).await
+    }
+
+    /// Runs `f` in a transaction like [`Context::transaction`](crate::Context::transaction).
+    /// As with [`AsyncConnection::transaction`], `f` returns a boxed future, e.g.,
+    /// `|cx| async move { .. }.scope_boxed()`.
+    pub async fn transaction<'a, F, R, E>(self: &mut Self[@cx], f: F) -> Result<R, E>
+    where
+        F: for<'r> FnOnce(&'r mut AsyncContext<T, U>[cx.user]) -> ScopedBoxFuture<'a, 'r, Result<R, E>>,
+        T::Conn: AsyncConnection,
+        E: From<diesel::result::Error>,
+    {
+        type Manager<C> = <C as AsyncConnection>::TransactionManager;
+
+        Manager::<T::Conn>::begin_transaction(self.inner.conn()).await?;
+        match f(self).await {
+            Ok(value) => {
+                Manager::<T::Conn>::commit_transaction(self.inner.conn()).await?;
+                Ok(value)
+            }
+            Err(err) => match Manager::<T::Conn>::rollback_transaction(self.inner.conn()).await {
+                Ok(()) | Err(diesel::result::Error::BrokenTransactionManager) => Err(err),
+                Err(rollback_err) => Err(rollback_err.into()),
+            },
+        }
+    }
+}
+
+);
@@ -0,0 +1,243 @@
+//! The operations of [`AsyncContext`](crate::AsyncContext), which check policies like
+//! [`guard`](crate::guard) does before deferring to [`async_bridge`].
+// Whether the futures are `Send` depends on the connection and the query, which is only known
+// where the traits are used with concrete types.
+#![allow(async_fn_in_trait)]
+
+use diesel::QueryResult;
+
+use crate::async_bridge;
+
+pub trait SelectList<Conn, Q, U>: Sized {
+    async fn select_list(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Vec<Self>>;
+}
+
+pub trait SelectFirst<Conn, Q, U>: Sized {
+    async fn select_first(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Option<Self>>;
+}
+
+pub trait SelectColumns<Conn, Q, S, O, U, V> {
+    async fn select_columns(conn: &mut Conn, user: &U, q: Q, columns: S) -> QueryResult<Vec<O>>;
+}
+
+pub trait UpdateWhere<Conn, Q, C, U> {
+    async fn update_where(conn: &mut Conn, user: &U, q: Q, v: C) -> QueryResult<usize>;
+}
+
+pub trait Insert<Conn, U>: Sized {
+    async fn insert(conn: &mut Conn, user: &U, v: Self) -> QueryResult<usize>;
+}
+
+pub trait DeleteWhere<Conn, Q, U> {
+    async fn delete_where(conn: &mut Conn, user: &U, q: Q) -> QueryResult<usize>;
+}
+
+#[cfg(not(feature = "runtime-checks"))]
+#[flux_rs::ignore]
+const _: () = {
+    impl<Conn, Q, U, R> SelectList<Conn, Q, U> for R
+    where
+        R: async_bridge::SelectList<Conn, Q>,
+    {
+        async fn select_list(conn: &mut Conn, _user: &U, q: Q) -> QueryResult<Vec<R>> {
+            <R as async_bridge::SelectList<Conn, Q>>::select_list(conn, q).await
+        }
+    }
+
+    impl<Conn, Q, U, R> SelectFirst<Conn, Q, U> for R
+    where
+        R: async_bridge::SelectFirst<Conn, Q>,
+    {
+        async fn select_first(conn: &mut Conn, _user: &U, q: Q) -> QueryResult<Option<R>> {
+            <R as async_bridge::SelectFirst<Conn, Q>>::select_first(conn, q).await
+        }
+    }
+
+    impl<Conn, Q, S, O, U, V, R> SelectColumns<Conn, Q, S, O, U, V> for R
+    where
+        R: async_bridge::SelectColumns<Conn, Q, S, O>,
+    {
+        async fn select_columns(
+            conn: &mut Conn,
+            _user: &U,
+            q: Q,
+            columns: S,
+        ) -> QueryResult<Vec<O>> {
+            <R as async_bridge::SelectColumns<Conn, Q, S, O>>::select_columns(conn, q, columns)
+                .await
+        }
+    }
+
+    impl<Conn, Q, C, U, R> UpdateWhere<Conn, Q, C, U> for R
+    where
+        R: async_bridge::UpdateWhere<Conn, Q, C>,
+    {
+        async fn update_where(conn: &mut Conn, _user: &U, q: Q, v: C) -> QueryResult<usize> {
+            <R as async_bridge::UpdateWhere<Conn, Q, C>>::update_where(conn, q, v).await
+        }
+    }
+
+    impl<Conn, U, R> Insert<Conn, U> for R
+    where
+        R: async_bridge::Insert<Conn>,
+    {
+        async fn insert(conn: &mut Conn, _user: &U, v: R) -> QueryResult<usize> {
+            <R as async_bridge::Insert<Conn>>::insert(conn, v).await
+        }
+    }
+
+    impl<Conn, Q, U, R> DeleteWhere<Conn, Q, U> for R
+    where
+        R: async_bridge::DeleteWhere<Conn, Q>,
+    {
+        async fn delete_where(conn: &mut Conn, _user: &U, q: Q) -> QueryResult<usize> {
+            <R as async_bridge::DeleteWhere<Conn, Q>>::delete_where(conn, q).await
+        }
+    }
+};
+
+#[cfg(feature = "runtime-checks")]
+#[flux_rs::ignore]
+const _: () = {
+    use diesel_async::{AsyncConnection, TransactionManager};
+
+    use crate::{
+        runtime::{
+            check, delete_filtered, read_filtered, update_filtered, ChangesetFilter,
+            ChangesetPolicy, DeleteFilter, Operation, PolicyViolation, ProjectionPolicy,
+            ReadFilter, RowPolicy,
+        },
+        And,
+    };
+
+    type Manager<Conn> = <Conn as AsyncConnection>::TransactionManager;
+
+    /// Commits the transaction begun on `conn` if `result` is `Ok` and rolls it back otherwise.
+    async fn finish<Conn: AsyncConnection, T>(
+        conn: &mut Conn,
+        result: QueryResult<T>,
+    ) -> QueryResult<T> {
+        match result {
+            Ok(value) => {
+                Manager::<Conn>::commit_transaction(conn).await?;
+                Ok(value)
+            }
+            Err(err) => match Manager::<Conn>::rollback_transaction(conn).await {
+                Ok(()) | Err(diesel::result::Error::BrokenTransactionManager) => Err(err),
+                Err(rollback_err) => Err(rollback_err),
+            },
+        }
+    }
+
+    impl<Conn, Q, U, R> SelectList<Conn, Q, U> for R
+    where
+        R: async_bridge::SelectList<Conn, And<Q, R::Filter>> + ReadFilter<U> + RowPolicy<U>,
+    {
+        async fn select_list(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Vec<R>> {
+            let q = read_filtered::<R, _, _>(user, q);
+            let rows = <R as async_bridge::SelectList<Conn, _>>::select_list(conn, q).await?;
+            check(&rows, Operation::Read, |row| row.allow_read(user))?;
+            Ok(rows)
+        }
+    }
+
+    impl<Conn, Q, U, R> SelectFirst<Conn, Q, U> for R
+    where
+        R: async_bridge::SelectFirst<Conn, And<Q, R::Filter>> + ReadFilter<U> + RowPolicy<U>,
+    {
+        async fn select_first(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Option<R>> {
+            let q = read_filtered::<R, _, _>(user, q);
+            let row = <R as async_bridge::SelectFirst<Conn, _>>::select_first(conn, q).await?;
+            check(&row, Operation::Read, |row| row.allow_read(user))?;
+            Ok(row)
+        }
+    }
+
+    impl<Conn, Q, S, O, U, V, R> SelectColumns<Conn, Q, S, O, U, V> for R
+    where
+        R: async_bridge::SelectColumns<Conn, Q, S, O> + async_bridge::SelectList<Conn, Q>,
+        S: ProjectionPolicy<R, U, V>,
+        Q: Clone,
+        Conn: AsyncConnection,
+    {
+        async fn select_columns(
+            conn: &mut Conn,
+            user: &U,
+            q: Q,
+            columns: S,
+        ) -> QueryResult<Vec<O>> {
+            Manager::<Conn>::begin_transaction(conn).await?;
+            let result = async {
+                let rows =
+                    <R as async_bridge::SelectList<Conn, Q>>::select_list(conn, q.clone()).await?;
+                check(&rows, Operation::Read, |row| S::allow_read(user, row))?;
+                <R as async_bridge::SelectColumns<Conn, Q, S, O>>::select_columns(conn, q, columns)
+                    .await
+            }
+            .await;
+            finish(conn, result).await
+        }
+    }
+
+    impl<Conn, Q, C, U, R> UpdateWhere<Conn, Q, C, U> for R
+    where
+        R: async_bridge::UpdateWhere<Conn, And<Q, C::Filter>, C>
+            + async_bridge::SelectList<Conn, Q>,
+        C: ChangesetPolicy<R, U> + ChangesetFilter<R, U>,
+        Q: Clone,
+        Conn: AsyncConnection,
+    {
+        async fn update_where(conn: &mut Conn, user: &U, q: Q, v: C) -> QueryResult<usize> {
+            if v.row_invariant_columns() > 1 {
+                return Err(PolicyViolation::new(Operation::Update).into());
+            }
+            Manager::<Conn>::begin_transaction(conn).await?;
+            let result = async {
+                let rows =
+                    <R as async_bridge::SelectList<Conn, Q>>::select_list(conn, q.clone()).await?;
+                check(&rows, Operation::Update, |row| {
+                    v.allow_update(user, row) && v.invariant(row)
+                })?;
+                let q = update_filtered(user, q, &v);
+                <R as async_bridge::UpdateWhere<Conn, _, C>>::update_where(conn, q, v).await
+            }
+            .await;
+            finish(conn, result).await
+        }
+    }
+
+    impl<Conn, U, R> Insert<Conn, U> for R
+    where
+        R: async_bridge::Insert<Conn> + RowPolicy<U>,
+    {
+        async fn insert(conn: &mut Conn, user: &U, v: R) -> QueryResult<usize> {
+            if !(v.allow_insert(user) && v.invariant()) {
+                return Err(PolicyViolation::new(Operation::Insert).into());
+            }
+            <R as async_bridge::Insert<Conn>>::insert(conn, v).await
+        }
+    }
+
+    impl<Conn, Q, U, R> DeleteWhere<Conn, Q, U> for R
+    where
+        R: async_bridge::DeleteWhere<Conn, And<Q, <R as DeleteFilter<U>>::Filter>>
+            + async_bridge::SelectList<Conn, Q>
+            + RowPolicy<U>
+            + DeleteFilter<U>,
+        Q: Clone,
+        Conn: AsyncConnection,
+    {
+        async fn delete_where(conn: &mut Conn, user: &U, q: Q) -> QueryResult<usize> {
+            Manager::<Conn>::begin_transaction(conn).await?;
+            let result = async {
+                let rows =
+                    <R as async_bridge::SelectList<Conn, Q>>::select_list(conn, q.clone()).await?;
+                check(&rows, Operation::Delete, |row| row.allow_delete(user))?;
+                let q = delete_filtered::<R, _, _>(user, q);
+                <R as async_bridge::DeleteWhere<Conn, _>>::delete_where(conn, q).await
+            }
+            .await;
+            finish(conn, result).await
+        }
+    }
+};
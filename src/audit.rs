@@ -0,0 +1,47 @@
+//! Compile-time checks that every table has policies.
+//!
+//! A column without a [`Field`] impl cannot be read with [`Context::select_columns`] or updated,
+//! and a table whose row type has no [`Row`] impl cannot be used with a [`Context`] at all. That
+//! is safe but easy to miss, e.g., when a migration adds a column. [`assert_policy_coverage!`]
+//! turns it into a compile error that names the offending column.
+//!
+//! [`Context`]: crate::Context
+//! [`Field`]: crate::Field
+//! [`Context::select_columns`]: crate::Context::select_columns
+//! [`assert_policy_coverage!`]: crate::assert_policy_coverage
+use crate::{Projection, Row};
+
+/// Fails to compile unless the row type has a [`Row`] impl and every column in `C` has a
+/// [`Field`] impl, i.e., unless `C` is a [`Projection`]. Use it through
+/// [`assert_policy_coverage!`](crate::assert_policy_coverage).
+pub const fn assert_covered<C, R, U, V>()
+where
+    C: Projection<R, U, V>,
+    R: Row<U>,
+{
+}
+
+/// Asserts at compile time that a table is fully covered by policies, i.e., that its row type
+/// implements [`Row`] and each of its columns implements [`Field`].
+///
+/// ```ignore
+/// rdiesel::assert_policy_coverage!(schema::wishes::table, Wish, User);
+/// ```
+///
+/// Columns that should never be read or updated through [`select_columns`] or [`update_where`]
+/// still need a `Field` impl, which can simply deny everything, e.g., `update(id: i32): false` in
+/// [`policy!`](crate::policy). This makes the decision explicit for every column.
+///
+/// [`select_columns`]: crate::Context::select_columns
+/// [`update_where`]: crate::Context::update_where
+#[macro_export]
+macro_rules! assert_policy_coverage {
+    ($table:ty, $row:ty, $user:ty $(,)?) => {
+        const _: () = $crate::audit::assert_covered::<
+            <$table as ::diesel::Table>::AllColumns,
+            $row,
+            $user,
+            _,
+        >();
+    };
+}
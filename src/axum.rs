@@ -0,0 +1,185 @@
+//! Axum integration, enabled by the `axum` feature.
+//!
+//! An [`AxumContext`] is extracted from the parts of a request: the user is resolved with an
+//! [`Authenticator`] and the connection is checked out of the [`DbPool`] in the state of the
+//! router, see [`PooledSession`]. Requests that are not authenticated are rejected with
+//! `401 Unauthorized`. The authenticator can also prepare the connection for the user, e.g.,
+//! for row-level security:
+//!
+//! ```ignore
+//! struct CookieAuth;
+//!
+//! #[axum::async_trait]
+//! impl Authenticator<PgConnection> for CookieAuth {
+//!     type User = User;
+//!
+//!     async fn authenticate(parts: &mut Parts, pool: &DbPool<PgConnection>) -> Option<User> {
+//!         let id: i32 = session_user_id(&parts.headers)?;
+//!         let pool = pool.clone();
+//!         tokio::task::spawn_blocking(move || users::table.find(id).first(&mut pool.get().ok()?).ok())
+//!             .await
+//!             .ok()?
+//!     }
+//!
+//!     fn on_enter(conn: &mut PgConnection, user: &User) -> QueryResult<()> {
+//!         pg::set_user(conn, &UserSettings::new(), user)
+//!     }
+//!
+//!     fn on_exit(conn: &mut PgConnection, user: &User) -> QueryResult<()> {
+//!         pg::reset_user(conn, &UserSettings::new(), user)
+//!     }
+//! }
+//!
+//! async fn wishes(mut cx: AxumContext<PgConnection, CookieAuth>) -> Json<Vec<Wish>> {
+//!     let user = cx.auth_user();
+//!     Json(cx.select_list(wishes::owner.eq(user.id)).unwrap())
+//! }
+//!
+//! let app = Router::new().route("/wishes", get(wishes)).with_state(pool);
+//! ```
+//!
+//! The queries of a [`Context`] block, so handlers that run many of them should do so in
+//! `tokio::task::spawn_blocking` or use an [`AsyncContext`](crate::AsyncContext) instead.
+use std::{fmt, marker::PhantomData};
+
+use ::axum::{
+    extract::{FromRef, FromRequestParts},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use diesel::{r2d2::R2D2Connection, QueryResult};
+
+pub use crate::pool::DbPool;
+use crate::{pool::PooledSession, Context, ContextImpl};
+
+/// The context extracted from a request, for connections of type `C` and users authenticated by
+/// `A`.
+pub type AxumContext<C, A> = Context<AxumSession<C, A>, <A as Authenticator<C>>::User>;
+
+/// Resolves the user of a request.
+#[::axum::async_trait]
+pub trait Authenticator<C: R2D2Connection + 'static>: 'static {
+    type User: Clone + Send + 'static;
+
+    /// Returns the user of the request, or `None` if it is not authenticated. `pool` is the pool
+    /// of the router, e.g., to look up the user.
+    async fn authenticate(parts: &mut Parts, pool: &DbPool<C>) -> Option<Self::User>;
+
+    /// Prepares the connection of the request for `user`, see [`ContextImpl::on_enter`]. For
+    /// PostgreSQL row-level security, call [`pg::set_user`](crate::pg::set_user) here and
+    /// [`pg::reset_user`](crate::pg::reset_user) in [`Authenticator::on_exit`].
+    fn on_enter(_conn: &mut C, _user: &Self::User) -> QueryResult<()> {
+        Ok(())
+    }
+
+    /// Undoes [`Authenticator::on_enter`] before the connection goes back to the pool, see
+    /// [`ContextImpl::on_exit`].
+    fn on_exit(_conn: &mut C, _user: &Self::User) -> QueryResult<()> {
+        Ok(())
+    }
+}
+
+/// The [`ContextImpl`] of an [`AxumContext`], a [`PooledSession`] for the users of `A` that is
+/// prepared with [`Authenticator::on_enter`].
+pub struct AxumSession<C: R2D2Connection + 'static, A: Authenticator<C>> {
+    session: PooledSession<C, A::User>,
+    _auth: PhantomData<fn() -> A>,
+}
+
+impl<C, A> ContextImpl for AxumSession<C, A>
+where
+    C: R2D2Connection + 'static,
+    A: Authenticator<C>,
+{
+    type User = A::User;
+    type Conn = C;
+
+    fn auth_user(&self) -> A::User {
+        self.session.auth_user()
+    }
+
+    fn conn(&mut self) -> &mut C {
+        self.session.conn()
+    }
+
+    fn on_enter(&mut self) -> QueryResult<()> {
+        let user = self.session.auth_user();
+        A::on_enter(self.session.conn(), &user)
+    }
+
+    fn on_exit(&mut self) -> QueryResult<()> {
+        let user = self.session.auth_user();
+        A::on_exit(self.session.conn(), &user)
+    }
+}
+
+/// Why an [`AxumContext`] could not be extracted.
+#[derive(Debug)]
+pub enum ContextRejection {
+    /// The request is not authenticated.
+    Unauthorized,
+    /// No connection could be checked out of the pool.
+    Pool(diesel::r2d2::PoolError),
+    /// The blocking task that checks out and prepares the connection panicked or was cancelled.
+    Task(tokio::task::JoinError),
+    /// [`ContextImpl::on_enter`] failed.
+    Database(diesel::result::Error),
+}
+
+impl fmt::Display for ContextRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextRejection::Unauthorized => f.write_str("request is not authenticated"),
+            ContextRejection::Pool(err) => write!(f, "failed to check out a connection: {err}"),
+            ContextRejection::Task(err) => write!(f, "failed to check out a connection: {err}"),
+            ContextRejection::Database(err) => write!(f, "failed to prepare the connection: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ContextRejection {}
+
+impl IntoResponse for ContextRejection {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ContextRejection::Unauthorized => StatusCode::UNAUTHORIZED,
+            ContextRejection::Pool(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ContextRejection::Task(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ContextRejection::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+#[flux_rs::ignore]
+const _: () = {
+    #[::axum::async_trait]
+    impl<S, C, A> FromRequestParts<S> for Context<AxumSession<C, A>, A::User>
+    where
+        S: Send + Sync,
+        DbPool<C>: FromRef<S>,
+        C: R2D2Connection + 'static,
+        A: Authenticator<C>,
+    {
+        type Rejection = ContextRejection;
+
+        async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+            let pool = DbPool::<C>::from_ref(state);
+            let user = A::authenticate(parts, &pool)
+                .await
+                .ok_or(ContextRejection::Unauthorized)?;
+            // Checking out a connection blocks until one is available, and `on_enter` runs queries.
+            tokio::task::spawn_blocking(move || {
+                let session =
+                    PooledSession::checkout(&pool, user).map_err(ContextRejection::Pool)?;
+                let session = AxumSession {
+                    session,
+                    _auth: PhantomData,
+                };
+                Context::new(session).map_err(ContextRejection::Database)
+            })
+            .await
+            .map_err(ContextRejection::Task)?
+        }
+    }
+};
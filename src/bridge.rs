@@ -1,15 +1,22 @@
 use diesel::{
     associations::HasTable,
-    dsl::Limit,
-    expression::AsExpression,
-    query_builder::{AsQuery, InsertStatement, IntoUpdateTarget, UpdateStatement},
-    query_dsl::methods::{ExecuteDsl, FilterDsl, LimitDsl, LoadQuery},
-    sql_types::{Bool, SingleValue, SqlType},
+    dsl::{AsExprOf, Limit, SqlTypeOf},
+    expression::{AsExpression, TypedExpressionType},
+    query_builder::{AsQuery, DeleteStatement, InsertStatement, IntoUpdateTarget, UpdateStatement},
+    query_dsl::methods::{ExecuteDsl, FilterDsl, LimitDsl, LoadQuery, SelectDsl},
+    sql_types::{
+        is_nullable::{IsOneNullable, IsSqlTypeNullable},
+        Bool, BoolOrNullableBool, MaybeNullableType, OneIsNullable, SingleValue, SqlType,
+    },
     AppearsOnTable, AsChangeset, BoolExpressionMethods as _, Column, Connection, Expression,
     ExpressionMethods as _, Insertable, OptionalExtension, QueryResult, RunQueryDsl,
+    TextExpressionMethods,
 };
 
-use crate::{And, Assign, Eq, EqAny, Gt, Lt, Or};
+use crate::{
+    And, Assign, Between, Eq, EqAny, Ge, Gt, IsNotNull, IsNull, Le, Like, Lt, Ne, NeAny, Not,
+    NotLike, Or,
+};
 
 pub trait ToDiesel {
     type DieselType;
@@ -25,6 +32,10 @@ pub trait SelectFirst<'query, Conn, Q>: Sized {
     fn select_first(conn: &mut Conn, q: Q) -> QueryResult<Option<Self>>;
 }
 
+pub trait SelectColumns<'query, Conn, Q, S, O> {
+    fn select_columns(conn: &mut Conn, q: Q, columns: S) -> QueryResult<Vec<O>>;
+}
+
 pub trait UpdateWhere<Conn, Q, C> {
     fn update_where(conn: &mut Conn, q: Q, v: C) -> QueryResult<usize>;
 }
@@ -33,68 +44,168 @@ pub trait Insert<Conn> {
     fn insert(conn: &mut Conn, v: Self) -> QueryResult<usize>;
 }
 
+pub trait DeleteWhere<Conn, Q> {
+    fn delete_where(conn: &mut Conn, q: Q) -> QueryResult<usize>;
+}
+
+/// The statements that the traits above run, built in one place so that
+/// [`debug`](crate::debug) shows the SQL of the statements that are executed. Also used by
+/// `async_bridge`.
+pub trait SelectQuery<Q> {
+    type Query;
+
+    fn select_query(q: Q) -> Self::Query;
+}
+
+pub trait UpdateQuery<Q, C> {
+    type Query;
+
+    fn update_query(q: Q, v: C) -> Self::Query;
+}
+
+pub trait InsertQuery {
+    type Query;
+
+    fn insert_query(v: Self) -> Self::Query;
+}
+
+pub trait DeleteQuery<Q> {
+    type Query;
+
+    fn delete_query(q: Q) -> Self::Query;
+}
+
+pub type Select<R, Q> = <R as SelectQuery<Q>>::Query;
+pub type Update<R, Q, C> = <R as UpdateQuery<Q, C>>::Query;
+pub type InsertInto<R> = <R as InsertQuery>::Query;
+pub type Delete<R, Q> = <R as DeleteQuery<Q>>::Query;
+
 #[flux_rs::ignore]
 const _: () = {
-    impl<'query, Conn, R, Q> SelectList<'query, Conn, Q> for R
+    impl<R, Q> SelectQuery<Q> for R
     where
         R: HasTable,
         Q: ToDiesel,
-        R::Table: FilterDsl<<Q as ToDiesel>::DieselType>,
-        <R::Table as FilterDsl<<Q as ToDiesel>::DieselType>>::Output: LoadQuery<'query, Conn, R>,
+        R::Table: FilterDsl<Q::DieselType>,
+    {
+        type Query = <R::Table as FilterDsl<Q::DieselType>>::Output;
+
+        fn select_query(q: Q) -> Self::Query {
+            diesel::QueryDsl::filter(R::table(), q.to_diesel())
+        }
+    }
+
+    impl<R, Q, C> UpdateQuery<Q, C> for R
+    where
+        R: SelectQuery<Q>,
+        Select<R, Q>: IntoUpdateTarget,
+        C: AsChangeset<Target = <Select<R, Q> as HasTable>::Table>,
+        UpdateStatement<
+            <Select<R, Q> as HasTable>::Table,
+            <Select<R, Q> as IntoUpdateTarget>::WhereClause,
+            C::Changeset,
+        >: AsQuery,
+    {
+        type Query = UpdateStatement<
+            <Select<R, Q> as HasTable>::Table,
+            <Select<R, Q> as IntoUpdateTarget>::WhereClause,
+            C::Changeset,
+        >;
+
+        fn update_query(q: Q, v: C) -> Self::Query {
+            diesel::update(R::select_query(q)).set(v)
+        }
+    }
+
+    impl<R> InsertQuery for R
+    where
+        R: HasTable + Insertable<R::Table>,
+    {
+        type Query = InsertStatement<R::Table, R::Values>;
+
+        fn insert_query(v: Self) -> Self::Query {
+            diesel::insert_into(R::table()).values(v)
+        }
+    }
+
+    impl<R, Q> DeleteQuery<Q> for R
+    where
+        R: SelectQuery<Q>,
+        Select<R, Q>: IntoUpdateTarget,
+    {
+        type Query = DeleteStatement<
+            <Select<R, Q> as HasTable>::Table,
+            <Select<R, Q> as IntoUpdateTarget>::WhereClause,
+        >;
+
+        fn delete_query(q: Q) -> Self::Query {
+            diesel::delete(R::select_query(q))
+        }
+    }
+
+    impl<'query, Conn, R, Q> SelectList<'query, Conn, Q> for R
+    where
+        R: SelectQuery<Q>,
+        Select<R, Q>: LoadQuery<'query, Conn, R>,
     {
         fn select_list(conn: &mut Conn, q: Q) -> QueryResult<Vec<R>> {
-            let filter = diesel::QueryDsl::filter(R::table(), q.to_diesel());
-            filter.load(conn)
+            R::select_query(q).load(conn)
         }
     }
 
     impl<'query, Conn, R, Q> SelectFirst<'query, Conn, Q> for R
     where
-        R: HasTable,
-        Q: ToDiesel,
-        R::Table: FilterDsl<Q::DieselType>,
-        <R::Table as FilterDsl<Q::DieselType>>::Output: LimitDsl,
-        Limit<<R::Table as FilterDsl<Q::DieselType>>::Output>: LoadQuery<'query, Conn, R>,
+        R: SelectQuery<Q>,
+        Select<R, Q>: LimitDsl,
+        Limit<Select<R, Q>>: LoadQuery<'query, Conn, R>,
     {
         fn select_first(conn: &mut Conn, q: Q) -> QueryResult<Option<R>> {
-            diesel::QueryDsl::filter(R::table(), q.to_diesel())
-                .limit(1)
-                .get_result(conn)
-                .optional()
+            R::select_query(q).limit(1).get_result(conn).optional()
+        }
+    }
+
+    impl<'query, Conn, R, Q, S, O> SelectColumns<'query, Conn, Q, S, O> for R
+    where
+        R: SelectQuery<Q>,
+        S: Expression,
+        Select<R, Q>: SelectDsl<S>,
+        <Select<R, Q> as SelectDsl<S>>::Output: LoadQuery<'query, Conn, O>,
+    {
+        fn select_columns(conn: &mut Conn, q: Q, columns: S) -> QueryResult<Vec<O>> {
+            SelectDsl::select(R::select_query(q), columns).load(conn)
         }
     }
 
     impl<Conn, R, Q, C> UpdateWhere<Conn, Q, C> for R
     where
-        R: HasTable,
-        Q: ToDiesel,
-        R::Table: FilterDsl<Q::DieselType>,
-        <R::Table as FilterDsl<Q::DieselType>>::Output: IntoUpdateTarget,
+        R: UpdateQuery<Q, C>,
         Conn: Connection,
-        C: AsChangeset<
-            Target = <<R::Table as FilterDsl<Q::DieselType>>::Output as HasTable>::Table,
-        >,
-        UpdateStatement<
-            <<R::Table as FilterDsl<Q::DieselType>>::Output as HasTable>::Table,
-            <<R::Table as FilterDsl<Q::DieselType>>::Output as IntoUpdateTarget>::WhereClause,
-            C::Changeset,
-        >: AsQuery + ExecuteDsl<Conn>,
+        Update<R, Q, C>: ExecuteDsl<Conn>,
     {
         fn update_where(conn: &mut Conn, q: Q, v: C) -> QueryResult<usize> {
-            let filter = diesel::QueryDsl::filter(R::table(), q.to_diesel());
-            diesel::update(filter).set(v).execute(conn)
+            ExecuteDsl::execute(R::update_query(q, v), conn)
         }
     }
 
     impl<Conn, R> Insert<Conn> for R
     where
-        R: HasTable,
-        R: Insertable<R::Table>,
-        Conn: diesel::Connection,
-        InsertStatement<R::Table, R::Values>: ExecuteDsl<Conn>,
+        R: InsertQuery,
+        Conn: Connection,
+        InsertInto<R>: ExecuteDsl<Conn>,
     {
         fn insert(conn: &mut Conn, v: Self) -> QueryResult<usize> {
-            diesel::insert_into(R::table()).values(v).execute(conn)
+            ExecuteDsl::execute(R::insert_query(v), conn)
+        }
+    }
+
+    impl<Conn, R, Q> DeleteWhere<Conn, Q> for R
+    where
+        R: DeleteQuery<Q>,
+        Conn: Connection,
+        Delete<R, Q>: ExecuteDsl<Conn>,
+    {
+        fn delete_where(conn: &mut Conn, q: Q) -> QueryResult<usize> {
+            ExecuteDsl::execute(R::delete_query(q), conn)
         }
     }
 
@@ -137,14 +248,74 @@ const _: () = {
         }
     }
 
+    impl<V, A, B> ToDiesel for Ne<V, A, B>
+    where
+        A: Expression,
+        A::SqlType: SqlType + SingleValue,
+        B: AsExpression<A::SqlType>,
+    {
+        type DieselType = diesel::dsl::NotEq<A, B>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.lhs.ne(self.rhs)
+        }
+    }
+
+    impl<V, A, B> ToDiesel for Le<V, A, B>
+    where
+        A: Expression,
+        A::SqlType: SqlType + SingleValue,
+        B: AsExpression<A::SqlType>,
+    {
+        type DieselType = diesel::dsl::LtEq<A, B>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.lhs.le(self.rhs)
+        }
+    }
+
+    impl<V, A, B> ToDiesel for Ge<V, A, B>
+    where
+        A: Expression,
+        A::SqlType: SqlType + SingleValue,
+        B: AsExpression<A::SqlType>,
+    {
+        type DieselType = diesel::dsl::GtEq<A, B>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.lhs.ge(self.rhs)
+        }
+    }
+
+    impl<V, A, L, H> ToDiesel for Between<V, A, L, H>
+    where
+        A: Expression,
+        A::SqlType: SqlType + SingleValue,
+        L: AsExpression<A::SqlType>,
+        H: AsExpression<A::SqlType>,
+    {
+        type DieselType = diesel::dsl::Between<A, L, H>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.expr.between(self.lo, self.hi)
+        }
+    }
+
+    // Either side may be `Nullable<Bool>`, e.g., a comparison of a nullable column, and then so is
+    // the result, as in SQL.
     impl<A, B> ToDiesel for And<A, B>
     where
         A: ToDiesel,
         B: ToDiesel,
-        A::DieselType: Expression<SqlType = Bool>,
-        B::DieselType: Expression<SqlType = Bool>,
+        A::DieselType: Expression,
+        B::DieselType: Expression,
+        SqlTypeOf<A::DieselType>: SqlType + BoolOrNullableBool,
+        SqlTypeOf<B::DieselType>: SqlType + BoolOrNullableBool + TypedExpressionType,
+        IsSqlTypeNullable<SqlTypeOf<A::DieselType>>:
+            OneIsNullable<IsSqlTypeNullable<SqlTypeOf<B::DieselType>>>,
+        IsOneNullable<SqlTypeOf<A::DieselType>, SqlTypeOf<B::DieselType>>: MaybeNullableType<Bool>,
     {
-        type DieselType = diesel::dsl::And<A::DieselType, B::DieselType>;
+        type DieselType = diesel::dsl::And<A::DieselType, B::DieselType, SqlTypeOf<B::DieselType>>;
 
         fn to_diesel(self) -> Self::DieselType {
             self.lhs.to_diesel().and(self.rhs.to_diesel())
@@ -155,10 +326,15 @@ const _: () = {
     where
         A: ToDiesel,
         B: ToDiesel,
-        A::DieselType: Expression<SqlType = Bool>,
-        B::DieselType: Expression<SqlType = Bool>,
+        A::DieselType: Expression,
+        B::DieselType: Expression,
+        SqlTypeOf<A::DieselType>: SqlType + BoolOrNullableBool,
+        SqlTypeOf<B::DieselType>: SqlType + BoolOrNullableBool + TypedExpressionType,
+        IsSqlTypeNullable<SqlTypeOf<A::DieselType>>:
+            OneIsNullable<IsSqlTypeNullable<SqlTypeOf<B::DieselType>>>,
+        IsOneNullable<SqlTypeOf<A::DieselType>, SqlTypeOf<B::DieselType>>: MaybeNullableType<Bool>,
     {
-        type DieselType = diesel::dsl::Or<A::DieselType, B::DieselType>;
+        type DieselType = diesel::dsl::Or<A::DieselType, B::DieselType, SqlTypeOf<B::DieselType>>;
 
         fn to_diesel(self) -> Self::DieselType {
             self.lhs.to_diesel().or(self.rhs.to_diesel())
@@ -174,19 +350,104 @@ const _: () = {
         type DieselType = diesel::dsl::EqAny<T, Vec<V>>;
 
         fn to_diesel(self) -> Self::DieselType {
-            self.lhs.eq_any(self.rhs)
+            self.lhs.eq_any(self.rhs.items)
+        }
+    }
+
+    impl<A> ToDiesel for Not<A>
+    where
+        A: ToDiesel,
+        A::DieselType: Expression,
+        SqlTypeOf<A::DieselType>: BoolOrNullableBool,
+    {
+        type DieselType = diesel::dsl::not<A::DieselType>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            diesel::dsl::not(self.expr.to_diesel())
+        }
+    }
+
+    impl<V, T> ToDiesel for NeAny<V, T>
+    where
+        T: Expression,
+        T::SqlType: SqlType + SingleValue,
+        V: AsExpression<T::SqlType>,
+    {
+        type DieselType = diesel::dsl::NeAny<T, Vec<V>>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.lhs.ne_all(self.rhs.items)
+        }
+    }
+
+    impl<T> ToDiesel for IsNull<T>
+    where
+        T: Expression,
+        T::SqlType: SqlType + SingleValue,
+    {
+        type DieselType = diesel::dsl::IsNull<T>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.expr.is_null()
+        }
+    }
+
+    impl<T> ToDiesel for IsNotNull<T>
+    where
+        T: Expression,
+        T::SqlType: SqlType + SingleValue,
+    {
+        type DieselType = diesel::dsl::IsNotNull<T>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.expr.is_not_null()
+        }
+    }
+
+    impl<A, P> ToDiesel for Like<A, P>
+    where
+        A: TextExpressionMethods,
+        A::SqlType: SqlType,
+        P: AsExpression<A::SqlType>,
+    {
+        type DieselType = diesel::dsl::Like<A, P>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.expr.like(self.pattern)
+        }
+    }
+
+    impl<A, P> ToDiesel for NotLike<A, P>
+    where
+        A: TextExpressionMethods,
+        A::SqlType: SqlType,
+        P: AsExpression<A::SqlType>,
+    {
+        type DieselType = diesel::dsl::NotLike<A, P>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.expr.not_like(self.pattern)
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    impl<A, P> ToDiesel for crate::ILike<A, P>
+    where
+        A: diesel::PgTextExpressionMethods,
+        P: AsExpression<diesel::sql_types::Text>,
+    {
+        type DieselType = diesel::dsl::ILike<A, P>;
+
+        fn to_diesel(self) -> Self::DieselType {
+            self.expr.ilike(self.pattern)
         }
     }
 
     impl ToDiesel for bool {
-        type DieselType = diesel::expression::SqlLiteral<Bool>;
+        type DieselType = AsExprOf<bool, Bool>;
 
         fn to_diesel(self) -> Self::DieselType {
-            if self {
-                diesel::dsl::sql::<Bool>("1 = 1")
-            } else {
-                diesel::dsl::sql::<Bool>("1 = 2")
-            }
+            AsExpression::<Bool>::as_expression(self)
         }
     }
 
@@ -0,0 +1,115 @@
+//! The SQL, with its bind parameters, of the statements that the operations of
+//! [`Context`](crate::Context) run for `user` on the backend `DB`, including the filters that
+//! `runtime-checks` conjoins. The statements are built by the same code that executes them.
+//!
+//! Only meant for tests, and only available with the `debug-queries` feature.
+use diesel::{backend::Backend, query_builder::QueryFragment};
+
+use crate::bridge::{
+    Delete, DeleteQuery, InsertInto, InsertQuery, Select, SelectQuery, Update, UpdateQuery,
+};
+
+#[flux_rs::ignore]
+fn render<DB, T>(query: &T) -> String
+where
+    T: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    diesel::debug_query::<DB, _>(query).to_string()
+}
+
+/// The `SELECT` of [`Context::select_list`](crate::Context::select_list).
+#[cfg(not(feature = "runtime-checks"))]
+#[flux_rs::ignore]
+pub fn select_list<R, DB, U, Q>(_user: &U, q: Q) -> String
+where
+    R: SelectQuery<Q>,
+    Select<R, Q>: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    render::<DB, _>(&R::select_query(q))
+}
+
+/// The `SELECT` of [`Context::select_list`](crate::Context::select_list).
+#[cfg(feature = "runtime-checks")]
+#[flux_rs::ignore]
+pub fn select_list<R, DB, U, Q>(user: &U, q: Q) -> String
+where
+    R: SelectQuery<crate::And<Q, R::Filter>> + crate::runtime::ReadFilter<U>,
+    Select<R, crate::And<Q, R::Filter>>: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    let q = crate::runtime::read_filtered::<R, _, _>(user, q);
+    render::<DB, _>(&R::select_query(q))
+}
+
+/// The `UPDATE` of [`Context::update_where`](crate::Context::update_where).
+#[cfg(not(feature = "runtime-checks"))]
+#[flux_rs::ignore]
+pub fn update_where<R, DB, U, Q, C>(_user: &U, q: Q, v: C) -> String
+where
+    R: UpdateQuery<Q, C>,
+    Update<R, Q, C>: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    render::<DB, _>(&R::update_query(q, v))
+}
+
+/// The `UPDATE` of [`Context::update_where`](crate::Context::update_where).
+#[cfg(feature = "runtime-checks")]
+#[flux_rs::ignore]
+pub fn update_where<R, DB, U, Q, C>(user: &U, q: Q, v: C) -> String
+where
+    R: UpdateQuery<crate::And<Q, C::Filter>, C>,
+    C: crate::runtime::ChangesetFilter<R, U>,
+    Update<R, crate::And<Q, C::Filter>, C>: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    let q = crate::runtime::update_filtered(user, q, &v);
+    render::<DB, _>(&R::update_query(q, v))
+}
+
+/// The `INSERT` of [`Context::insert`](crate::Context::insert).
+#[flux_rs::ignore]
+pub fn insert<R, DB>(v: R) -> String
+where
+    R: InsertQuery,
+    InsertInto<R>: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    render::<DB, _>(&R::insert_query(v))
+}
+
+/// The `DELETE` of [`Context::delete_where`](crate::Context::delete_where).
+#[cfg(not(feature = "runtime-checks"))]
+#[flux_rs::ignore]
+pub fn delete_where<R, DB, U, Q>(_user: &U, q: Q) -> String
+where
+    R: DeleteQuery<Q>,
+    Delete<R, Q>: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    render::<DB, _>(&R::delete_query(q))
+}
+
+/// The `DELETE` of [`Context::delete_where`](crate::Context::delete_where).
+#[cfg(feature = "runtime-checks")]
+#[flux_rs::ignore]
+pub fn delete_where<R, DB, U, Q>(user: &U, q: Q) -> String
+where
+    R: DeleteQuery<crate::And<Q, <R as crate::runtime::DeleteFilter<U>>::Filter>>
+        + crate::runtime::DeleteFilter<U>,
+    Delete<R, crate::And<Q, <R as crate::runtime::DeleteFilter<U>>::Filter>>: QueryFragment<DB>,
+    DB: Backend + Default,
+    DB::QueryBuilder: Default,
+{
+    let q = crate::runtime::delete_filtered::<R, _, _>(user, q);
+    render::<DB, _>(&R::delete_query(q))
+}
@@ -0,0 +1,201 @@
+//! The operations of [`Context`](crate::Context). They defer to [`bridge`] directly unless the
+//! `runtime-checks` feature is enabled, in which case they first check the policies of the
+//! affected rows, see [`runtime`](crate::runtime).
+use diesel::QueryResult;
+
+use crate::bridge;
+
+pub trait SelectList<'query, Conn, Q, U>: Sized {
+    fn select_list(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Vec<Self>>;
+}
+
+pub trait SelectFirst<'query, Conn, Q, U>: Sized {
+    fn select_first(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Option<Self>>;
+}
+
+pub trait SelectColumns<'query, Conn, Q, S, O, U, V> {
+    fn select_columns(conn: &mut Conn, user: &U, q: Q, columns: S) -> QueryResult<Vec<O>>;
+}
+
+pub trait UpdateWhere<'query, Conn, Q, C, U> {
+    fn update_where(conn: &mut Conn, user: &U, q: Q, v: C) -> QueryResult<usize>;
+}
+
+pub trait Insert<Conn, U>: Sized {
+    fn insert(conn: &mut Conn, user: &U, v: Self) -> QueryResult<usize>;
+}
+
+pub trait DeleteWhere<'query, Conn, Q, U> {
+    fn delete_where(conn: &mut Conn, user: &U, q: Q) -> QueryResult<usize>;
+}
+
+#[cfg(not(feature = "runtime-checks"))]
+#[flux_rs::ignore]
+const _: () = {
+    impl<'query, Conn, Q, U, R> SelectList<'query, Conn, Q, U> for R
+    where
+        R: bridge::SelectList<'query, Conn, Q>,
+    {
+        fn select_list(conn: &mut Conn, _user: &U, q: Q) -> QueryResult<Vec<R>> {
+            <R as bridge::SelectList<'query, Conn, Q>>::select_list(conn, q)
+        }
+    }
+
+    impl<'query, Conn, Q, U, R> SelectFirst<'query, Conn, Q, U> for R
+    where
+        R: bridge::SelectFirst<'query, Conn, Q>,
+    {
+        fn select_first(conn: &mut Conn, _user: &U, q: Q) -> QueryResult<Option<R>> {
+            <R as bridge::SelectFirst<'query, Conn, Q>>::select_first(conn, q)
+        }
+    }
+
+    impl<'query, Conn, Q, S, O, U, V, R> SelectColumns<'query, Conn, Q, S, O, U, V> for R
+    where
+        R: bridge::SelectColumns<'query, Conn, Q, S, O>,
+    {
+        fn select_columns(conn: &mut Conn, _user: &U, q: Q, columns: S) -> QueryResult<Vec<O>> {
+            <R as bridge::SelectColumns<'query, Conn, Q, S, O>>::select_columns(conn, q, columns)
+        }
+    }
+
+    impl<'query, Conn, Q, C, U, R> UpdateWhere<'query, Conn, Q, C, U> for R
+    where
+        R: bridge::UpdateWhere<Conn, Q, C>,
+    {
+        fn update_where(conn: &mut Conn, _user: &U, q: Q, v: C) -> QueryResult<usize> {
+            <R as bridge::UpdateWhere<Conn, Q, C>>::update_where(conn, q, v)
+        }
+    }
+
+    impl<Conn, U, R> Insert<Conn, U> for R
+    where
+        R: bridge::Insert<Conn>,
+    {
+        fn insert(conn: &mut Conn, _user: &U, v: R) -> QueryResult<usize> {
+            <R as bridge::Insert<Conn>>::insert(conn, v)
+        }
+    }
+
+    impl<'query, Conn, Q, U, R> DeleteWhere<'query, Conn, Q, U> for R
+    where
+        R: bridge::DeleteWhere<Conn, Q>,
+    {
+        fn delete_where(conn: &mut Conn, _user: &U, q: Q) -> QueryResult<usize> {
+            <R as bridge::DeleteWhere<Conn, Q>>::delete_where(conn, q)
+        }
+    }
+};
+
+#[cfg(feature = "runtime-checks")]
+#[flux_rs::ignore]
+const _: () = {
+    use diesel::Connection;
+
+    use crate::{
+        runtime::{
+            check, delete_filtered, read_filtered, update_filtered, ChangesetFilter,
+            ChangesetPolicy, DeleteFilter, Operation, PolicyViolation, ProjectionPolicy,
+            ReadFilter, RowPolicy,
+        },
+        And,
+    };
+
+    impl<'query, Conn, Q, U, R> SelectList<'query, Conn, Q, U> for R
+    where
+        R: bridge::SelectList<'query, Conn, And<Q, R::Filter>> + ReadFilter<U> + RowPolicy<U>,
+    {
+        fn select_list(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Vec<R>> {
+            let q = read_filtered::<R, _, _>(user, q);
+            let rows = <R as bridge::SelectList<'query, Conn, _>>::select_list(conn, q)?;
+            check(&rows, Operation::Read, |row| row.allow_read(user))?;
+            Ok(rows)
+        }
+    }
+
+    impl<'query, Conn, Q, U, R> SelectFirst<'query, Conn, Q, U> for R
+    where
+        R: bridge::SelectFirst<'query, Conn, And<Q, R::Filter>> + ReadFilter<U> + RowPolicy<U>,
+    {
+        fn select_first(conn: &mut Conn, user: &U, q: Q) -> QueryResult<Option<R>> {
+            let q = read_filtered::<R, _, _>(user, q);
+            let row = <R as bridge::SelectFirst<'query, Conn, _>>::select_first(conn, q)?;
+            check(&row, Operation::Read, |row| row.allow_read(user))?;
+            Ok(row)
+        }
+    }
+
+    impl<'query, Conn, Q, S, O, U, V, R> SelectColumns<'query, Conn, Q, S, O, U, V> for R
+    where
+        R: bridge::SelectColumns<'query, Conn, Q, S, O> + bridge::SelectList<'query, Conn, Q>,
+        S: ProjectionPolicy<R, U, V>,
+        Q: Clone,
+        Conn: Connection,
+    {
+        fn select_columns(conn: &mut Conn, user: &U, q: Q, columns: S) -> QueryResult<Vec<O>> {
+            conn.transaction(|conn| {
+                let rows =
+                    <R as bridge::SelectList<'query, Conn, Q>>::select_list(conn, q.clone())?;
+                check(&rows, Operation::Read, |row| S::allow_read(user, row))?;
+                <R as bridge::SelectColumns<'query, Conn, Q, S, O>>::select_columns(
+                    conn, q, columns,
+                )
+            })
+        }
+    }
+
+    impl<'query, Conn, Q, C, U, R> UpdateWhere<'query, Conn, Q, C, U> for R
+    where
+        R: bridge::UpdateWhere<Conn, And<Q, C::Filter>, C> + bridge::SelectList<'query, Conn, Q>,
+        C: ChangesetPolicy<R, U> + ChangesetFilter<R, U>,
+        Q: Clone,
+        Conn: Connection,
+    {
+        fn update_where(conn: &mut Conn, user: &U, q: Q, v: C) -> QueryResult<usize> {
+            if v.row_invariant_columns() > 1 {
+                return Err(PolicyViolation::new(Operation::Update).into());
+            }
+            conn.transaction(|conn| {
+                let rows =
+                    <R as bridge::SelectList<'query, Conn, Q>>::select_list(conn, q.clone())?;
+                check(&rows, Operation::Update, |row| {
+                    v.allow_update(user, row) && v.invariant(row)
+                })?;
+                let q = update_filtered(user, q, &v);
+                <R as bridge::UpdateWhere<Conn, _, C>>::update_where(conn, q, v)
+            })
+        }
+    }
+
+    impl<Conn, U, R> Insert<Conn, U> for R
+    where
+        R: bridge::Insert<Conn> + RowPolicy<U>,
+    {
+        fn insert(conn: &mut Conn, user: &U, v: R) -> QueryResult<usize> {
+            if !(v.allow_insert(user) && v.invariant()) {
+                return Err(PolicyViolation::new(Operation::Insert).into());
+            }
+            <R as bridge::Insert<Conn>>::insert(conn, v)
+        }
+    }
+
+    impl<'query, Conn, Q, U, R> DeleteWhere<'query, Conn, Q, U> for R
+    where
+        R: bridge::DeleteWhere<Conn, And<Q, <R as DeleteFilter<U>>::Filter>>
+            + bridge::SelectList<'query, Conn, Q>
+            + RowPolicy<U>
+            + DeleteFilter<U>,
+        Q: Clone,
+        Conn: Connection,
+    {
+        fn delete_where(conn: &mut Conn, user: &U, q: Q) -> QueryResult<usize> {
+            conn.transaction(|conn| {
+                let rows =
+                    <R as bridge::SelectList<'query, Conn, Q>>::select_list(conn, q.clone())?;
+                check(&rows, Operation::Delete, |row| row.allow_delete(user))?;
+                let q = delete_filtered::<R, _, _>(user, q);
+                <R as bridge::DeleteWhere<Conn, _>>::delete_where(conn, q)
+            })
+        }
+    }
+};
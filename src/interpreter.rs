@@ -0,0 +1,304 @@
+//! Evaluation of [`Expr`](crate::Expr)s over rows in memory.
+//!
+//! [`ExprRuntime`] is the executable counterpart of `Expr::eval`: it computes the value of an
+//! expression for a Rust value of the row type, e.g., to filter rows that are already loaded or
+//! to check the SQL translation of a filter against the rows the database returns.
+//! `#[derive(RdieselModel)]` implements it for the columns of a model, and this module for
+//! literals and the expressions built with the methods of `Expr`.
+//!
+//! Evaluation follows SQL rather than the refinements: `NULL` is `None` and propagates through
+//! comparisons, and `And`, `Or` and `Not` use three-valued logic. A filter matches a row only if
+//! it evaluates to `Some(true)`, see [`ExprRuntime::matches`]. Pattern matches are not
+//! evaluated, as their case sensitivity and escape characters depend on the backend.
+use crate::{And, Between, Eq, EqAny, Ge, Gt, IsNotNull, IsNull, Le, Lt, Ne, NeAny, Not, Or};
+
+/// An expression that can be evaluated for a row of type `R`.
+pub trait ExprRuntime<R> {
+    /// The type of the non-null values of the expression.
+    type Value;
+
+    /// The value of the expression for `row`, or `None` if it is `NULL`.
+    fn eval(&self, row: &R) -> Option<Self::Value>;
+
+    /// Whether `row` satisfies this filter, i.e., whether the database would return it.
+    fn matches(&self, row: &R) -> bool
+    where
+        Self: ExprRuntime<R, Value = bool>,
+    {
+        self.eval(row) == Some(true)
+    }
+}
+
+/// Compares the values of `lhs` and `rhs`, which is `NULL` if either is.
+fn compare<R, A, B>(
+    lhs: &A,
+    rhs: &B,
+    row: &R,
+    op: impl FnOnce(&A::Value, &A::Value) -> bool,
+) -> Option<bool>
+where
+    A: ExprRuntime<R>,
+    B: ExprRuntime<R, Value = A::Value>,
+{
+    Some(op(&lhs.eval(row)?, &rhs.eval(row)?))
+}
+
+/// `lhs AND rhs`, which is false if either side is, even if the other is `NULL`.
+fn and(lhs: Option<bool>, rhs: Option<bool>) -> Option<bool> {
+    match (lhs, rhs) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+/// `lhs OR rhs`, which is true if either side is, even if the other is `NULL`.
+fn or(lhs: Option<bool>, rhs: Option<bool>) -> Option<bool> {
+    match (lhs, rhs) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+#[flux_rs::ignore]
+const _: () = {
+    impl<R, A, B> ExprRuntime<R> for And<A, B>
+    where
+        A: ExprRuntime<R, Value = bool>,
+        B: ExprRuntime<R, Value = bool>,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            and(self.lhs.eval(row), self.rhs.eval(row))
+        }
+    }
+
+    impl<R, A, B> ExprRuntime<R> for Or<A, B>
+    where
+        A: ExprRuntime<R, Value = bool>,
+        B: ExprRuntime<R, Value = bool>,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            or(self.lhs.eval(row), self.rhs.eval(row))
+        }
+    }
+
+    impl<R, A> ExprRuntime<R> for Not<A>
+    where
+        A: ExprRuntime<R, Value = bool>,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            self.expr.eval(row).map(|b| !b)
+        }
+    }
+
+    impl<R, V, A, B> ExprRuntime<R> for Eq<V, A, B>
+    where
+        A: ExprRuntime<R, Value = V>,
+        B: ExprRuntime<R, Value = V>,
+        V: PartialEq,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            compare(&self.lhs, &self.rhs, row, |l, r| l == r)
+        }
+    }
+
+    impl<R, V, A, B> ExprRuntime<R> for Ne<V, A, B>
+    where
+        A: ExprRuntime<R, Value = V>,
+        B: ExprRuntime<R, Value = V>,
+        V: PartialEq,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            compare(&self.lhs, &self.rhs, row, |l, r| l != r)
+        }
+    }
+
+    impl<R, V, A, B> ExprRuntime<R> for Lt<V, A, B>
+    where
+        A: ExprRuntime<R, Value = V>,
+        B: ExprRuntime<R, Value = V>,
+        V: PartialOrd,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            compare(&self.lhs, &self.rhs, row, |l, r| l < r)
+        }
+    }
+
+    impl<R, V, A, B> ExprRuntime<R> for Gt<V, A, B>
+    where
+        A: ExprRuntime<R, Value = V>,
+        B: ExprRuntime<R, Value = V>,
+        V: PartialOrd,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            compare(&self.lhs, &self.rhs, row, |l, r| l > r)
+        }
+    }
+
+    impl<R, V, A, B> ExprRuntime<R> for Le<V, A, B>
+    where
+        A: ExprRuntime<R, Value = V>,
+        B: ExprRuntime<R, Value = V>,
+        V: PartialOrd,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            compare(&self.lhs, &self.rhs, row, |l, r| l <= r)
+        }
+    }
+
+    impl<R, V, A, B> ExprRuntime<R> for Ge<V, A, B>
+    where
+        A: ExprRuntime<R, Value = V>,
+        B: ExprRuntime<R, Value = V>,
+        V: PartialOrd,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            compare(&self.lhs, &self.rhs, row, |l, r| l >= r)
+        }
+    }
+
+    // `expr BETWEEN lo AND hi` is `lo <= expr AND expr <= hi`, so it is false rather than `NULL`
+    // when one bound is `NULL` and the other excludes the value.
+    impl<R, V, A, L, H> ExprRuntime<R> for Between<V, A, L, H>
+    where
+        A: ExprRuntime<R, Value = V>,
+        L: ExprRuntime<R, Value = V>,
+        H: ExprRuntime<R, Value = V>,
+        V: PartialOrd,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            and(
+                compare(&self.lo, &self.expr, row, |lo, v| lo <= v),
+                compare(&self.expr, &self.hi, row, |v, hi| v <= hi),
+            )
+        }
+    }
+
+    impl<R, V, T> ExprRuntime<R> for EqAny<V, T>
+    where
+        T: ExprRuntime<R, Value = V>,
+        V: PartialEq,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            let val = self.lhs.eval(row)?;
+            Some(self.rhs.items.contains(&val))
+        }
+    }
+
+    impl<R, V, T> ExprRuntime<R> for NeAny<V, T>
+    where
+        T: ExprRuntime<R, Value = V>,
+        V: PartialEq,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            let val = self.lhs.eval(row)?;
+            Some(!self.rhs.items.contains(&val))
+        }
+    }
+
+    impl<R, T> ExprRuntime<R> for IsNull<T>
+    where
+        T: ExprRuntime<R>,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            Some(self.expr.eval(row).is_none())
+        }
+    }
+
+    impl<R, T> ExprRuntime<R> for IsNotNull<T>
+    where
+        T: ExprRuntime<R>,
+    {
+        type Value = bool;
+
+        fn eval(&self, row: &R) -> Option<bool> {
+            Some(self.expr.eval(row).is_some())
+        }
+    }
+
+    impl<R> ExprRuntime<R> for &str {
+        type Value = String;
+
+        fn eval(&self, _row: &R) -> Option<String> {
+            Some(self.to_string())
+        }
+    }
+
+    impl<R, V: Clone> ExprRuntime<R> for Option<V> {
+        type Value = V;
+
+        fn eval(&self, _row: &R) -> Option<V> {
+            self.clone()
+        }
+    }
+};
+
+/// Literals evaluate to themselves.
+macro_rules! impl_literal {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl<R> ExprRuntime<R> for $ty {
+                type Value = $ty;
+
+                fn eval(&self, _row: &R) -> Option<$ty> {
+                    Some(self.clone())
+                }
+            }
+        )*
+    };
+}
+
+#[flux_rs::ignore]
+const _: () = {
+    impl_literal!(i16, i32, i64, bool, f32, f64, String);
+
+    #[cfg(feature = "chrono")]
+    impl_literal!(chrono::NaiveDate, chrono::NaiveTime, chrono::NaiveDateTime);
+
+    #[cfg(feature = "chrono")]
+    impl<R, Tz: chrono::TimeZone> ExprRuntime<R> for chrono::DateTime<Tz> {
+        type Value = chrono::DateTime<Tz>;
+
+        fn eval(&self, _row: &R) -> Option<chrono::DateTime<Tz>> {
+            Some(self.clone())
+        }
+    }
+
+    #[cfg(feature = "time")]
+    impl_literal!(
+        time::Date,
+        time::Time,
+        time::PrimitiveDateTime,
+        time::OffsetDateTime,
+    );
+
+    #[cfg(feature = "uuid")]
+    impl_literal!(uuid::Uuid);
+};
@@ -1,6 +1,50 @@
-use diesel::QueryResult;
+use diesel::{connection::TransactionManager, Connection, QueryResult};
 use flux_rs::*;
+#[cfg(feature = "async")]
+mod async_bridge;
+#[cfg(feature = "async")]
+mod async_context;
+#[cfg(feature = "async")]
+mod async_guard;
+pub mod audit;
+#[cfg(feature = "axum")]
+pub mod axum;
 mod bridge;
+#[cfg(feature = "debug-queries")]
+pub mod debug;
+mod guard;
+pub mod interpreter;
+#[cfg(feature = "postgres")]
+pub mod pg;
+#[cfg(feature = "r2d2")]
+pub mod pool;
+pub mod rls;
+#[cfg(feature = "rocket")]
+pub mod rocket;
+#[cfg(feature = "runtime-checks")]
+pub mod runtime;
+
+#[cfg(feature = "async")]
+pub use async_context::{AsyncContext, AsyncContextImpl};
+pub use rdiesel_derive::{policy, RdieselModel};
+
+/// Expands to its input if the `runtime-checks` feature is enabled. Used by the derive macros,
+/// which cannot see the features of this crate.
+#[doc(hidden)]
+#[cfg(feature = "runtime-checks")]
+#[macro_export]
+macro_rules! __if_runtime_checks {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[doc(hidden)]
+#[cfg(not(feature = "runtime-checks"))]
+#[macro_export]
+macro_rules! __if_runtime_checks {
+    ($($item:item)*) => {};
+}
 
 /// Dummy trait implemented for every type that can be used as a bound to trick Flux into not
 /// generating a kvar when instantiating a type parameter.
@@ -15,12 +59,35 @@ pub trait ContextImpl {
     fn auth_user(&self) -> Self::User;
 
     fn conn(self: &mut Self) -> &mut Self::Conn;
+
+    /// Runs when a [`Context`] is created, before any query. Use it to prepare the connection for
+    /// the user, e.g., to set the session variables read by row-level security policies, see
+    /// [`pg::set_user`].
+    fn on_enter(&mut self) -> QueryResult<()> {
+        Ok(())
+    }
+
+    /// Runs when a [`Context`] is dropped, or when [`ContextImpl::on_enter`] fails. Use it to undo
+    /// `on_enter` so that a pooled connection is not reused with the identity of the previous
+    /// user. Errors are ignored.
+    fn on_exit(&mut self) -> QueryResult<()> {
+        Ok(())
+    }
+}
+
+defs! {
+    // Pattern matches are uninterpreted: `like(value, pattern)` holds when `value LIKE pattern`.
+    fn like(value: str, pattern: str) -> bool;
+    fn ilike(value: str, pattern: str) -> bool;
 }
 
 flux!(
 
 #[opaque]
-pub struct Context<T, U>[user: U] {
+pub struct Context<T, U>[user: U]
+where
+    T: ContextImpl,
+{
     _u: std::marker::PhantomData<U>,
     inner: T,
 }
@@ -32,11 +99,17 @@ where
     T: ContextImpl<User = U>,
     U: NoKvar,
 {
-    pub fn new(inner: T) -> Self {
-        Self {
+    /// Creates a context for the user of `inner` and runs [`ContextImpl::on_enter`]. If it fails,
+    /// [`ContextImpl::on_exit`] runs as well, to undo whatever `on_enter` did before failing.
+    pub fn new(mut inner: T) -> QueryResult<Self> {
+        if let Err(err) = inner.on_enter() {
+            let _ = inner.on_exit();
+            return Err(err);
+        }
+        Ok(Self {
             _u: std::marker::PhantomData,
             inner,
-        }
+        })
     }
 
     pub fn auth_user(self: &Self[@cx]) -> U[cx.user] {
@@ -46,47 +119,126 @@ where
     pub fn select_list<'query, R as base, Q as base>(
         self: &mut Self[@cx],
         q: Q,
-    ) -> QueryResult<Vec<R{row: <Q as Expr<R, bool>>::eval(q, row)}>>
+    ) -> QueryResult<Vec<R{row: <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::allow_read(cx.user, row) && <R as Row<U>>::invariant(row)}>>
     where
         Q: Expr<R, bool>,
-        R: bridge::SelectList<'query, T::Conn, Q>,
+        R: guard::SelectList<'query, T::Conn, Q, U> + Row<U>,
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <R as Row<U>>::allow_read(cx.user, row)
     {
-        R::select_list(self.inner.conn(), q)
+        let user = self.inner.auth_user();
+        R::select_list(self.inner.conn(), &user, q)
     }
 
     pub fn select_first<'query, R as base, Q as base>(
         self: &mut Self[@cx],
         q: Q,
-    ) -> QueryResult<Option<R{row: <Q as Expr<R, bool>>::eval(q, row)}>>
+    ) -> QueryResult<Option<R{row: <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::allow_read(cx.user, row) && <R as Row<U>>::invariant(row)}>>
+    where
+        Q: Expr<R, bool>,
+        R: guard::SelectFirst<'query, T::Conn, Q, U> + Row<U>,
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <R as Row<U>>::allow_read(cx.user, row)
+    {
+        let user = self.inner.auth_user();
+        R::select_first(self.inner.conn(), &user, q)
+    }
+
+    pub fn select_columns<'query, R as base, Q as base, S, V, O>(
+        self: &mut Self[@cx],
+        q: Q,
+        columns: S,
+    ) -> QueryResult<Vec<O>>
     where
         Q: Expr<R, bool>,
-        R: bridge::SelectFirst<'query, T::Conn, Q>,
+        S: Projection<R, U, V>,
+        R: guard::SelectColumns<'query, T::Conn, Q, S, O, U, V> + Row<U>,
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <S as Projection<R, U, V>>::allow_read(cx.user, row)
     {
-        R::select_first(self.inner.conn(), q)
+        let user = self.inner.auth_user();
+        R::select_columns(self.inner.conn(), &user, q, columns)
     }
 
-    pub fn update_where<R as base, Q as base, C>(self: &mut Self[@cx], q: Q, v: C) -> QueryResult<usize>
+    pub fn update_where<'query, R as base, Q as base, C as base>(self: &mut Self[@cx], q: Q, v: C) -> QueryResult<usize>
     where
         Q: Expr<R, bool>,
         C: Changeset<R, U>,
-        R: bridge::UpdateWhere<T::Conn, Q, C>
-    requires forall row. <Q as Expr<R, bool>>::eval(q, row) => <C as Changeset<R, U>>::allow_update(cx.user, row)
+        R: guard::UpdateWhere<'query, T::Conn, Q, C, U> + Row<U>
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) =>
+        <C as Changeset<R, U>>::allow_update(cx.user, row, v) && <C as Changeset<R, U>>::invariant(row, v)
+    {
+        let user = self.inner.auth_user();
+        R::update_where(self.inner.conn(), &user, q, v)
+    }
+
+    pub fn insert<R as base>(self: &mut Self[@cx], v: R{ <R as Row<U>>::allow_insert(cx.user, v) && <R as Row<U>>::invariant(v) }) -> QueryResult<usize>
+    where
+        R: guard::Insert<T::Conn, U> + Row<U>
     {
-        R::update_where(self.inner.conn(), q, v)
+        let user = self.inner.auth_user();
+        R::insert(self.inner.conn(), &user, v)
     }
 
-    pub fn insert<R as base>(self: &mut Self[@cx], v: R{ <R as Row<U>>::allow_insert(cx.user, v) }) -> QueryResult<usize>
+    pub fn delete_where<'query, R as base, Q as base>(self: &mut Self[@cx], q: Q) -> QueryResult<usize>
     where
-        R: bridge::Insert<T::Conn> + Row<U>
+        Q: Expr<R, bool>,
+        R: guard::DeleteWhere<'query, T::Conn, Q, U> + Row<U>
+    requires forall row. <Q as Expr<R, bool>>::eval(q, row) && <R as Row<U>>::invariant(row) => <R as Row<U>>::allow_delete(cx.user, row)
     {
-        R::insert(self.inner.conn(), v)
+        let user = self.inner.auth_user();
+        R::delete_where(self.inner.conn(), &user, q)
+    }
+
+    /// Runs `f` in a transaction, which is committed if `f` returns `Ok` and rolled back
+    /// otherwise. `f` gets this context back, so the queries it runs are checked for the same
+    /// user. Nested transactions use savepoints.
+    pub fn transaction<F, R, E>(self: &mut Self[@cx], f: F) -> Result<R, E>
+    where
+        F: FnOnce(&mut Context<T, U>[cx.user]) -> Result<R, E>,
+        T::Conn: Connection,
+        E: From<diesel::result::Error>,
+    {
+        type Manager<C> = <C as Connection>::TransactionManager;
+
+        Manager::<T::Conn>::begin_transaction(self.inner.conn())?;
+        match f(self) {
+            Ok(value) => {
+                Manager::<T::Conn>::commit_transaction(self.inner.conn())?;
+                Ok(value)
+            }
+            Err(err) => match Manager::<T::Conn>::rollback_transaction(self.inner.conn()) {
+                Ok(()) | Err(diesel::result::Error::BrokenTransactionManager) => Err(err),
+                Err(rollback_err) => Err(rollback_err.into()),
+            },
+        }
+    }
+}
+
+#[trusted]
+impl<T, U> Drop for Context<T, U>
+where
+    T: ContextImpl,
+{
+    fn drop(&mut self) {
+        let _ = self.inner.on_exit();
     }
 }
 
 
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has no policies for user `{U}`",
+    label = "missing `Row<{U}>` impl",
+    note = "declare its policies, e.g., with `rdiesel::policy!`"
+)]
 #[generics(Self as base, U as base)]
 pub trait Row<U> {
     reft allow_insert(user: U, row: Self) -> bool;
+
+    reft allow_read(user: U, row: Self) -> bool { false }
+
+    reft allow_delete(user: U, row: Self) -> bool { false }
+
+    /// Holds for every row stored in the table, e.g., because of a `CHECK` constraint. It is
+    /// assumed for rows read from the database and must be established by inserts and updates.
+    reft invariant(row: Self) -> bool { true }
 }
 
 #[generics(Self as base, R as base, V as base)]
@@ -108,6 +260,17 @@ where
         }
     }
 
+    fn ne<T as base>(self: Self, rhs: T) -> Ne<V, Self, T>[self, rhs]
+    where
+        T: NoKvar
+    {
+        Ne {
+            _val: std::marker::PhantomData,
+            lhs: self,
+            rhs,
+        }
+    }
+
     fn lt<T as base>(self: Self, rhs: T) -> Lt<V, Self, T>[self, rhs]
     where
         T: NoKvar
@@ -130,10 +293,49 @@ where
         }
     }
 
-    fn eq_any(self: Self, rhs: Vec<V>) -> EqAny<V, Self> {
+    fn le<T as base>(self: Self, rhs: T) -> Le<V, Self, T>[self, rhs]
+    where
+        T: NoKvar
+    {
+        Le {
+            _val: std::marker::PhantomData,
+            lhs: self,
+            rhs,
+        }
+    }
+
+    fn ge<T as base>(self: Self, rhs: T) -> Ge<V, Self, T>[self, rhs]
+    where
+        T: NoKvar
+    {
+        Ge {
+            _val: std::marker::PhantomData,
+            lhs: self,
+            rhs,
+        }
+    }
+
+    fn between<L as base, H as base>(self: Self, lo: L, hi: H) -> Between<V, Self, L, H>[self, lo, hi]
+    where
+        L: NoKvar,
+        H: NoKvar,
+    {
+        Between {
+            _val: std::marker::PhantomData,
+            expr: self,
+            lo,
+            hi,
+        }
+    }
+
+    fn eq_any(self: Self, rhs: ValueSet<V>) -> EqAny<V, Self>[self, rhs] {
         EqAny { lhs: self, rhs }
     }
 
+    fn ne_any(self: Self, rhs: ValueSet<V>) -> NeAny<V, Self>[self, rhs] {
+        NeAny { lhs: self, rhs }
+    }
+
     fn and<T as base>(self: Self, rhs: T) -> And<Self, T>[self, rhs]
     where
         Self: Expr<R, bool>,
@@ -149,14 +351,83 @@ where
     {
         Or { lhs: self, rhs }
     }
+
+    fn like<P as base>(self: Self, pattern: P) -> Like<Self, P>[self, pattern]
+    where
+        P: NoKvar
+    {
+        Like { expr: self, pattern }
+    }
+
+    fn not_like<P as base>(self: Self, pattern: P) -> NotLike<Self, P>[self, pattern]
+    where
+        P: NoKvar
+    {
+        NotLike { expr: self, pattern }
+    }
+
+    #[cfg(feature = "postgres")]
+    fn ilike<P as base>(self: Self, pattern: P) -> ILike<Self, P>[self, pattern]
+    where
+        P: NoKvar
+    {
+        ILike { expr: self, pattern }
+    }
+
+    fn not(self: Self) -> Not<Self>[self]
+    where
+        Self: Expr<R, bool>,
+    {
+        Not { expr: self }
+    }
+}
+
+/// A column of SQL type `Nullable<_>`.
+///
+/// A nullable column implements [`Expr<R, V>`] for the type `V` of its non-null values, and
+/// its `eval` is an arbitrary value when the column is `NULL`. SQL never matches a row on which a
+/// comparison evaluates to `NULL`, so `eval` holds for every row the database returns, but it
+/// may also hold for a row with a `NULL` column that the database does not return. Results are
+/// thus precise up to nullness, which can be recovered with [`NullableExpr::is_not_null`], and
+/// preconditions on affected rows are checked conservatively.
+#[generics(Self as base, R as base)]
+pub trait NullableExpr<R>: Sized
+where
+    R: NoKvar,
+{
+    reft null(expr: Self, row: R) -> bool;
+
+    fn is_null(self: Self) -> IsNull<Self>[self] {
+        IsNull { expr: self }
+    }
+
+    fn is_not_null(self: Self) -> IsNotNull<Self>[self] {
+        IsNotNull { expr: self }
+    }
 }
 
+#[diagnostic::on_unimplemented(
+    message = "column `{Self}` has no policies for row `{R}` and user `{U}`",
+    label = "missing `Field<{R}, {U}, _>` impl",
+    note = "declare its policies, e.g., with `rdiesel::policy!`"
+)]
 #[trusted]
-#[generics(R as base, U as base)]
-pub trait Field<R, U>: Sized {
-    reft allow_update(user: U, row: R) -> bool;
+#[generics(R as base, U as base, V as base)]
+pub trait Field<R, U, V>: Sized {
+    reft allow_update(user: U, row: R, val: V) -> bool;
+
+    reft allow_read(user: U, row: R) -> bool { false }
+
+    /// Must hold of a value assigned to this column for the row invariant to be preserved.
+    /// Defaults to `false`, so a column without it cannot be updated.
+    reft invariant(row: R, val: V) -> bool { false }
 
-    fn assign<V as base>(self: Self, v: V) -> Assign<Self, V> {
+    /// Whether the row invariant mentions this column. [`invariant`](Field::invariant) is checked
+    /// against the old values of the other columns, so a [`Changeset`] may assign at most one
+    /// such column. Defaults to `true`.
+    reft in_row_invariant(row: R, val: V) -> bool { true }
+
+    fn assign(self: Self, v: V) -> Assign<Self, V>[self, v] {
         Assign {
             field: self,
             val: v,
@@ -165,34 +436,68 @@ pub trait Field<R, U>: Sized {
 }
 
 
-#[generics(R as base, U as base)]
+#[generics(Self as base, R as base, U as base)]
 pub trait Changeset<R, U> {
-    reft allow_update(user: U, row: R) -> bool;
+    reft allow_update(user: U, row: R, changes: Self) -> bool;
+
+    reft invariant(row: R, changes: Self) -> bool;
+
+    /// The number of columns assigned by `changes` that the row invariant mentions.
+    reft row_invariant_columns(row: R, changes: Self) -> int;
 }
 
-#[generics(R as base, U as base)]
-impl<F, V, R, U> Changeset<R, U> for Assign<F, V> where F: Field<R, U> {
-    reft allow_update(user: U, row: R) -> bool {
-        <F as Field<R, U>>::allow_update(user, row)
+#[generics(R as base, U as base, F as base, V as base)]
+impl<F, V, R, U> Changeset<R, U> for Assign<F, V> where F: Field<R, U, V> {
+    reft allow_update(user: U, row: R, changes: Assign<F, V>) -> bool {
+        <F as Field<R, U, V>>::allow_update(user, row, changes.val)
+    }
+
+    reft invariant(row: R, changes: Assign<F, V>) -> bool {
+        <F as Field<R, U, V>>::invariant(row, changes.val)
+    }
+
+    reft row_invariant_columns(row: R, changes: Assign<F, V>) -> int {
+        if <F as Field<R, U, V>>::in_row_invariant(row, changes.val) { 1 } else { 0 }
     }
 }
 
-#[generics(R as base, U as base)]
+#[generics(R as base, U as base, A as base, B as base)]
 impl<A, B, R, U> Changeset<R, U> for (A, B)
 where
     A: Changeset<R, U>,
     B: Changeset<R, U>,
 {
-    reft allow_update(user: U, row: R) -> bool {
-        <A as Changeset<R, U>>::allow_update(user, row) && <B as Changeset<R, U>>::allow_update(user, row)
+    reft allow_update(user: U, row: R, changes: (A, B)) -> bool {
+        <A as Changeset<R, U>>::allow_update(user, row, changes.0)
+            && <B as Changeset<R, U>>::allow_update(user, row, changes.1)
     }
+
+    reft invariant(row: R, changes: (A, B)) -> bool {
+        <A as Changeset<R, U>>::invariant(row, changes.0)
+            && <B as Changeset<R, U>>::invariant(row, changes.1)
+            && <A as Changeset<R, U>>::row_invariant_columns(row, changes.0)
+                + <B as Changeset<R, U>>::row_invariant_columns(row, changes.1) <= 1
+    }
+
+    reft row_invariant_columns(row: R, changes: (A, B)) -> int {
+        <A as Changeset<R, U>>::row_invariant_columns(row, changes.0)
+            + <B as Changeset<R, U>>::row_invariant_columns(row, changes.1)
+    }
+}
+
+/// A tuple of columns that can be loaded with [`Context::select_columns`]. `V` is the tuple of
+/// the columns' value types.
+#[generics(R as base, U as base)]
+pub trait Projection<R, U, V> {
+    reft allow_read(user: U, row: R) -> bool;
 }
 
-pub struct Assign<F, V> {
-    field: F,
-    val: V,
+pub struct Assign<F, V>[field: F, val: V] {
+    field: F[field],
+    val: V[val],
 }
 
+#[derive(Clone)]
 pub struct And<A, B>[lhs: A, rhs: B] {
     lhs: A[lhs],
     rhs: B[rhs],
@@ -210,6 +515,7 @@ where
     }
 }
 
+#[derive(Clone)]
 pub struct Or<A, B>[lhs: A, rhs: B] {
     lhs: A[lhs],
     rhs: B[rhs],
@@ -227,6 +533,7 @@ where
 
 }
 
+#[derive(Clone)]
 pub struct Eq<V, A, B>[lhs: A, rhs: B] {
     lhs: A[lhs],
     rhs: B[rhs],
@@ -245,6 +552,7 @@ where
 
 }
 
+#[derive(Clone)]
 pub struct Gt<V, A, B>[lhs: A, rhs: B] {
     lhs: A[lhs],
     rhs: B[rhs],
@@ -263,6 +571,7 @@ where
 
 }
 
+#[derive(Clone)]
 pub struct Lt<V, A, B>[lhs: A, rhs: B] {
     lhs: A[lhs],
     rhs: B[rhs],
@@ -280,14 +589,245 @@ where
     }
 }
 
-pub struct EqAny<V, T> {
-    lhs: T,
-    rhs: Vec<V>,
+#[derive(Clone)]
+pub struct Ne<V, A, B>[lhs: A, rhs: B] {
+    lhs: A[lhs],
+    rhs: B[rhs],
+    _val: std::marker::PhantomData<V>,
+}
+
+#[generics(R as base, A as base, B as base, V as base)]
+impl<R, A, B, V> Expr<R, bool> for Ne<V, A, B>
+where
+    A: Expr<R, V>,
+    B: Expr<R, V>,
+{
+    reft eval(expr: Ne<A, B>, row: R) -> bool {
+        <A as Expr<R, V>>::eval(expr.lhs, row) != <B as Expr<R, V>>::eval(expr.rhs, row)
+    }
+}
+
+#[derive(Clone)]
+pub struct Le<V, A, B>[lhs: A, rhs: B] {
+    lhs: A[lhs],
+    rhs: B[rhs],
+    _val: std::marker::PhantomData<V>,
+}
+
+#[generics(R as base, A as base, B as base, V as base)]
+impl<R, A, B, V> Expr<R, bool> for Le<V, A, B>
+where
+    A: Expr<R, V>,
+    B: Expr<R, V>,
+{
+    reft eval(expr: Le<A, B>, row: R) -> bool {
+        <A as Expr<R, V>>::eval(expr.lhs, row) <= <B as Expr<R, V>>::eval(expr.rhs, row)
+    }
+}
+
+#[derive(Clone)]
+pub struct Ge<V, A, B>[lhs: A, rhs: B] {
+    lhs: A[lhs],
+    rhs: B[rhs],
+    _val: std::marker::PhantomData<V>,
+}
+
+#[generics(R as base, A as base, B as base, V as base)]
+impl<R, A, B, V> Expr<R, bool> for Ge<V, A, B>
+where
+    A: Expr<R, V>,
+    B: Expr<R, V>,
+{
+    reft eval(expr: Ge<A, B>, row: R) -> bool {
+        <A as Expr<R, V>>::eval(expr.lhs, row) >= <B as Expr<R, V>>::eval(expr.rhs, row)
+    }
+}
+
+#[derive(Clone)]
+pub struct Between<V, A, L, H>[expr: A, lo: L, hi: H] {
+    expr: A[expr],
+    lo: L[lo],
+    hi: H[hi],
+    _val: std::marker::PhantomData<V>,
+}
+
+#[generics(R as base, A as base, L as base, H as base, V as base)]
+impl<R, A, L, H, V> Expr<R, bool> for Between<V, A, L, H>
+where
+    A: Expr<R, V>,
+    L: Expr<R, V>,
+    H: Expr<R, V>,
+{
+    reft eval(expr: Between<A, L, H>, row: R) -> bool {
+        <L as Expr<R, V>>::eval(expr.lo, row) <= <A as Expr<R, V>>::eval(expr.expr, row)
+            && <A as Expr<R, V>>::eval(expr.expr, row) <= <H as Expr<R, V>>::eval(expr.hi, row)
+    }
+}
+
+#[derive(Clone)]
+pub struct Not<A>[expr: A] {
+    expr: A[expr],
+}
+
+#[generics(R as base, A as base)]
+impl<R, A> Expr<R, bool> for Not<A>
+where
+    A: Expr<R, bool>,
+{
+    reft eval(expr: Not<A>, row: R) -> bool {
+        !<A as Expr<R, bool>>::eval(expr.expr, row)
+    }
+}
+
+#[derive(Clone)]
+pub struct EqAny<V, T>[lhs: T, rhs: Set<V>] {
+    lhs: T[lhs],
+    rhs: ValueSet<V>[rhs],
 }
 
 #[generics(R as base, T as base, V as base)]
 impl<R, T, V> Expr<R, bool> for EqAny<V, T> where T: Expr<R, V> {
-    reft eval(expr: EqAny, row: R) -> bool { true }
+    reft eval(expr: EqAny<V, T>, row: R) -> bool {
+        set_is_in(<T as Expr<R, V>>::eval(expr.lhs, row), expr.rhs)
+    }
+}
+
+#[derive(Clone)]
+pub struct Like<A, P>[expr: A, pattern: P] {
+    expr: A[expr],
+    pattern: P[pattern],
+}
+
+#[generics(R as base, A as base, P as base)]
+impl<R, A, P> Expr<R, bool> for Like<A, P>
+where
+    A: Expr<R, String>,
+    P: Expr<R, String>,
+{
+    reft eval(expr: Like<A, P>, row: R) -> bool {
+        like(<A as Expr<R, String>>::eval(expr.expr, row), <P as Expr<R, String>>::eval(expr.pattern, row))
+    }
+}
+
+#[derive(Clone)]
+pub struct NotLike<A, P>[expr: A, pattern: P] {
+    expr: A[expr],
+    pattern: P[pattern],
+}
+
+#[generics(R as base, A as base, P as base)]
+impl<R, A, P> Expr<R, bool> for NotLike<A, P>
+where
+    A: Expr<R, String>,
+    P: Expr<R, String>,
+{
+    reft eval(expr: NotLike<A, P>, row: R) -> bool {
+        !like(<A as Expr<R, String>>::eval(expr.expr, row), <P as Expr<R, String>>::eval(expr.pattern, row))
+    }
+}
+
+#[cfg(feature = "postgres")]
+#[derive(Clone)]
+pub struct ILike<A, P>[expr: A, pattern: P] {
+    expr: A[expr],
+    pattern: P[pattern],
+}
+
+#[cfg(feature = "postgres")]
+#[generics(R as base, A as base, P as base)]
+impl<R, A, P> Expr<R, bool> for ILike<A, P>
+where
+    A: Expr<R, String>,
+    P: Expr<R, String>,
+{
+    reft eval(expr: ILike<A, P>, row: R) -> bool {
+        ilike(<A as Expr<R, String>>::eval(expr.expr, row), <P as Expr<R, String>>::eval(expr.pattern, row))
+    }
+}
+
+#[derive(Clone)]
+pub struct IsNull<T>[expr: T] {
+    expr: T[expr],
+}
+
+#[generics(R as base, T as base)]
+impl<R, T> Expr<R, bool> for IsNull<T> where T: NullableExpr<R> {
+    reft eval(expr: IsNull<T>, row: R) -> bool {
+        <T as NullableExpr<R>>::null(expr.expr, row)
+    }
+}
+
+#[derive(Clone)]
+pub struct IsNotNull<T>[expr: T] {
+    expr: T[expr],
+}
+
+#[generics(R as base, T as base)]
+impl<R, T> Expr<R, bool> for IsNotNull<T> where T: NullableExpr<R> {
+    reft eval(expr: IsNotNull<T>, row: R) -> bool {
+        !<T as NullableExpr<R>>::null(expr.expr, row)
+    }
+}
+
+#[derive(Clone)]
+pub struct NeAny<V, T>[lhs: T, rhs: Set<V>] {
+    lhs: T[lhs],
+    rhs: ValueSet<V>[rhs],
+}
+
+#[generics(R as base, T as base, V as base)]
+impl<R, T, V> Expr<R, bool> for NeAny<V, T> where T: Expr<R, V> {
+    reft eval(expr: NeAny<V, T>, row: R) -> bool {
+        !set_is_in(<T as Expr<R, V>>::eval(expr.lhs, row), expr.rhs)
+    }
+}
+
+/// The right-hand side of [`Expr::eq_any`] and [`Expr::ne_any`], refined by the set of values it contains.
+#[opaque]
+pub struct ValueSet<V>[elems: Set<V>] {
+    items: Vec<V>,
+}
+
+#[trusted]
+#[generics(V as base)]
+impl<V> ValueSet<V> {
+    pub fn new() -> ValueSet<V>[set_empty(0)] {
+        ValueSet { items: Vec::new() }
+    }
+
+    pub fn insert(self: &strg ValueSet<V>[@s], v: V)
+    ensures self: ValueSet<V>[set_union(s, set_singleton(v))]
+    {
+        self.items.push(v);
+    }
+
+    pub fn contains(self: &ValueSet<V>[@s], v: &V[@x]) -> bool[set_is_in(x, s)]
+    where
+        V: PartialEq,
+    {
+        self.items.contains(v)
+    }
+}
+
+#[trusted]
+#[generics(V as base)]
+impl<V: Clone> Clone for ValueSet<V> {
+    fn clone(self: &ValueSet<V>[@s]) -> ValueSet<V>[s] {
+        ValueSet { items: self.items.clone() }
+    }
+}
+
+impl<V> Default for ValueSet<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[trusted]
+impl<V> FromIterator<V> for ValueSet<V> {
+    fn from_iter<I: IntoIterator<Item = V>>(iter: I) -> Self {
+        ValueSet { items: iter.into_iter().collect() }
+    }
 }
 
 #[generics(R as base)]
@@ -295,11 +835,136 @@ impl<R> Expr<R, i32> for i32 {
     reft eval(val: Self, row: R) -> int { val }
 }
 
+#[generics(R as base)]
+impl<R> Expr<R, i16> for i16 {
+    reft eval(val: Self, row: R) -> int { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, i64> for i64 {
+    reft eval(val: Self, row: R) -> int { val }
+}
+
 #[generics(R as base)]
 impl<R> Expr<R, bool> for bool {
     reft eval(val: Self, row: R) -> bool { val }
 }
 
-impl<R> Expr<R, String> for String {}
+#[generics(R as base)]
+impl<R> Expr<R, String> for String {
+    reft eval(val: Self, row: R) -> str { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, String> for &str {
+    reft eval(val: Self, row: R) -> str { val }
+}
+
+// The values of the types below are uninterpreted sorts named after the type, like fields of those
+// types in `RdieselModel`.
+
+#[generics(R as base)]
+impl<R> Expr<R, f32> for f32 {
+    reft eval(val: Self, row: R) -> f32 { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, f64> for f64 {
+    reft eval(val: Self, row: R) -> f64 { val }
+}
+
+/// Comparing with `None` is never true in SQL, so the value of an optional literal is left
+/// unspecified.
+impl<R, V> Expr<R, V> for Option<V> {}
+
+);
+
+macro_rules! impl_projection {
+    () => {};
+    ($col:ident $val:ident $(, $cols:ident $vals:ident)*) => {
+        flux!(
+            #[generics(R as base, U as base)]
+            impl<R, U, $col, $val, $($cols, $vals),*> Projection<R, U, ($val, $($vals,)*)>
+                for ($col, $($cols,)*)
+            where
+                $col: Field<R, U, $val>,
+                $($cols: Field<R, U, $vals>,)*
+            {
+                reft allow_read(user: U, row: R) -> bool {
+                    <$col as Field<R, U, $val>>::allow_read(user, row)
+                        $(&& <$cols as Field<R, U, $vals>>::allow_read(user, row))*
+                }
+            }
+        );
+
+        impl_projection!($($cols $vals),*);
+    };
+}
+
+// Diesel supports tables with up to 32 columns by default.
+impl_projection!(
+    C0 V0, C1 V1, C2 V2, C3 V3, C4 V4, C5 V5, C6 V6, C7 V7,
+    C8 V8, C9 V9, C10 V10, C11 V11, C12 V12, C13 V13, C14 V14, C15 V15,
+    C16 V16, C17 V17, C18 V18, C19 V19, C20 V20, C21 V21, C22 V22, C23 V23,
+    C24 V24, C25 V25, C26 V26, C27 V27, C28 V28, C29 V29, C30 V30, C31 V31
+);
+
+#[cfg(feature = "chrono")]
+flux!(
+
+#[generics(R as base)]
+impl<R> Expr<R, chrono::NaiveDate> for chrono::NaiveDate {
+    reft eval(val: Self, row: R) -> chrono::NaiveDate { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, chrono::NaiveTime> for chrono::NaiveTime {
+    reft eval(val: Self, row: R) -> chrono::NaiveTime { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, chrono::NaiveDateTime> for chrono::NaiveDateTime {
+    reft eval(val: Self, row: R) -> chrono::NaiveDateTime { val }
+}
+
+#[generics(R as base, Tz as base)]
+impl<R, Tz: chrono::TimeZone> Expr<R, chrono::DateTime<Tz>> for chrono::DateTime<Tz> {
+    reft eval(val: Self, row: R) -> chrono::DateTime<Tz> { val }
+}
+
+);
+
+#[cfg(feature = "time")]
+flux!(
+
+#[generics(R as base)]
+impl<R> Expr<R, time::Date> for time::Date {
+    reft eval(val: Self, row: R) -> time::Date { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, time::Time> for time::Time {
+    reft eval(val: Self, row: R) -> time::Time { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, time::PrimitiveDateTime> for time::PrimitiveDateTime {
+    reft eval(val: Self, row: R) -> time::PrimitiveDateTime { val }
+}
+
+#[generics(R as base)]
+impl<R> Expr<R, time::OffsetDateTime> for time::OffsetDateTime {
+    reft eval(val: Self, row: R) -> time::OffsetDateTime { val }
+}
+
+);
+
+#[cfg(feature = "uuid")]
+flux!(
+
+#[generics(R as base)]
+impl<R> Expr<R, uuid::Uuid> for uuid::Uuid {
+    reft eval(val: Self, row: R) -> uuid::Uuid { val }
+}
 
 );
@@ -0,0 +1,116 @@
+//! PostgreSQL session variables that expose the authenticated user to the database, e.g., to the
+//! row-level security policies generated by [`rls`](crate::rls) or to audit triggers.
+//!
+//! [`set_user`] stores every field of a [`SessionUser`] in a setting of the connection, named as
+//! in [`UserSettings`], and [`reset_user`] clears them. The settings are set for the session, not
+//! for the current transaction as with `SET LOCAL`, because a [`Context`](crate::Context) spans
+//! many transactions. They must be reset before the connection is used for another user, which
+//! [`PgSession`] does when its context is dropped. With the `r2d2` feature, [`UserSettings`] also
+//! does so as the hooks of a [`PooledSession`](crate::pool::PooledSession).
+use diesel::{pg::Pg, sql_types::Text, Connection, QueryResult, RunQueryDsl};
+
+use crate::{rls::UserSettings, ContextImpl};
+
+/// A user whose fields are exposed to the database as session variables.
+pub trait SessionUser {
+    /// The fields read by the database, as pairs of a field name and its value.
+    fn session_fields(&self) -> Vec<(&'static str, String)>;
+}
+
+/// Sets the settings of the fields of `user` on `conn`.
+pub fn set_user<C, U>(conn: &mut C, settings: &UserSettings, user: &U) -> QueryResult<()>
+where
+    C: Connection<Backend = Pg>,
+    U: SessionUser,
+{
+    for (field, value) in user.session_fields() {
+        set_config(conn, &settings.setting(field), &value)?;
+    }
+    Ok(())
+}
+
+/// Clears the settings of the fields of `user` on `conn`. Policies that read them deny every
+/// row until they are set again.
+pub fn reset_user<C, U>(conn: &mut C, settings: &UserSettings, user: &U) -> QueryResult<()>
+where
+    C: Connection<Backend = Pg>,
+    U: SessionUser,
+{
+    for (field, _) in user.session_fields() {
+        set_config(conn, &settings.setting(field), "")?;
+    }
+    Ok(())
+}
+
+fn set_config<C>(conn: &mut C, name: &str, value: &str) -> QueryResult<()>
+where
+    C: Connection<Backend = Pg>,
+{
+    diesel::sql_query("SELECT set_config($1, $2, false)")
+        .bind::<Text, _>(name)
+        .bind::<Text, _>(value)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// A [`ContextImpl`] over a PostgreSQL connection that sets the session variables of the user
+/// when a context is created and resets them when it is dropped.
+pub struct PgSession<C, U> {
+    conn: C,
+    user: U,
+    settings: UserSettings,
+}
+
+impl<C, U> PgSession<C, U> {
+    pub fn new(conn: C, user: U) -> Self {
+        Self::with_settings(conn, user, UserSettings::new())
+    }
+
+    pub fn with_settings(conn: C, user: U, settings: UserSettings) -> Self {
+        Self {
+            conn,
+            user,
+            settings,
+        }
+    }
+}
+
+impl<C, U> ContextImpl for PgSession<C, U>
+where
+    C: Connection<Backend = Pg>,
+    U: SessionUser + Clone,
+{
+    type User = U;
+    type Conn = C;
+
+    fn auth_user(&self) -> U {
+        self.user.clone()
+    }
+
+    fn conn(&mut self) -> &mut C {
+        &mut self.conn
+    }
+
+    fn on_enter(&mut self) -> QueryResult<()> {
+        set_user(&mut self.conn, &self.settings, &self.user)
+    }
+
+    fn on_exit(&mut self) -> QueryResult<()> {
+        reset_user(&mut self.conn, &self.settings, &self.user)
+    }
+}
+
+#[cfg(feature = "r2d2")]
+impl<C, U> crate::pool::SessionHooks<C, U> for UserSettings
+where
+    C: Connection<Backend = Pg>,
+    U: SessionUser,
+{
+    fn on_enter(&self, conn: &mut C, user: &U) -> QueryResult<()> {
+        set_user(conn, self, user)
+    }
+
+    fn on_exit(&self, conn: &mut C, user: &U) -> QueryResult<()> {
+        reset_user(conn, self, user)
+    }
+}
@@ -0,0 +1,87 @@
+//! Contexts over pooled connections, enabled by the `r2d2` feature.
+use diesel::{
+    r2d2::{ConnectionManager, Pool, PoolError, PooledConnection, R2D2Connection},
+    QueryResult,
+};
+
+use crate::ContextImpl;
+
+/// An r2d2 pool of diesel connections of type `C`.
+pub type DbPool<C> = Pool<ConnectionManager<C>>;
+
+/// Prepares a pooled connection for a user and undoes it before the connection goes back to the
+/// pool, see [`ContextImpl::on_enter`] and [`ContextImpl::on_exit`]. `()` does nothing, and with
+/// the `postgres` feature [`UserSettings`](crate::rls::UserSettings) sets the session variables
+/// of the user, see [`pg::set_user`](crate::pg::set_user).
+pub trait SessionHooks<C, U> {
+    fn on_enter(&self, conn: &mut C, user: &U) -> QueryResult<()>;
+
+    fn on_exit(&self, conn: &mut C, user: &U) -> QueryResult<()>;
+}
+
+impl<C, U> SessionHooks<C, U> for () {
+    fn on_enter(&self, _conn: &mut C, _user: &U) -> QueryResult<()> {
+        Ok(())
+    }
+
+    fn on_exit(&self, _conn: &mut C, _user: &U) -> QueryResult<()> {
+        Ok(())
+    }
+}
+
+/// A [`ContextImpl`] over a connection checked out of a [`DbPool`], which is returned to the pool
+/// when the context is dropped. `H` prepares the connection for the user.
+pub struct PooledSession<C: R2D2Connection + 'static, U, H = ()> {
+    conn: PooledConnection<ConnectionManager<C>>,
+    user: U,
+    hooks: H,
+}
+
+impl<C: R2D2Connection + 'static, U> PooledSession<C, U> {
+    pub fn new(conn: PooledConnection<ConnectionManager<C>>, user: U) -> Self {
+        Self::with_hooks(conn, user, ())
+    }
+
+    /// Checks out a connection for `user`, blocking until one is available.
+    pub fn checkout(pool: &DbPool<C>, user: U) -> Result<Self, PoolError> {
+        Self::checkout_with_hooks(pool, user, ())
+    }
+}
+
+impl<C: R2D2Connection + 'static, U, H> PooledSession<C, U, H> {
+    pub fn with_hooks(conn: PooledConnection<ConnectionManager<C>>, user: U, hooks: H) -> Self {
+        Self { conn, user, hooks }
+    }
+
+    /// Checks out a connection for `user` that is prepared with `hooks`, blocking until one is
+    /// available.
+    pub fn checkout_with_hooks(pool: &DbPool<C>, user: U, hooks: H) -> Result<Self, PoolError> {
+        Ok(Self::with_hooks(pool.get()?, user, hooks))
+    }
+}
+
+impl<C, U, H> ContextImpl for PooledSession<C, U, H>
+where
+    C: R2D2Connection + 'static,
+    U: Clone,
+    H: SessionHooks<C, U>,
+{
+    type User = U;
+    type Conn = C;
+
+    fn auth_user(&self) -> U {
+        self.user.clone()
+    }
+
+    fn conn(&mut self) -> &mut C {
+        &mut self.conn
+    }
+
+    fn on_enter(&mut self) -> QueryResult<()> {
+        self.hooks.on_enter(&mut self.conn, &self.user)
+    }
+
+    fn on_exit(&mut self) -> QueryResult<()> {
+        self.hooks.on_exit(&mut self.conn, &self.user)
+    }
+}
@@ -0,0 +1,255 @@
+//! PostgreSQL row-level security policies generated from the policies declared with
+//! [`policy!`](crate::policy).
+//!
+//! For every table, `policy!` implements [`RowLevelSecurity`] for the table, which translates its
+//! `read`, `insert`, `update` and `delete` clauses to the `USING` and `WITH CHECK` expressions of
+//! a `CREATE POLICY` statement. A [`Migration`] collects the tables and renders the statements as
+//! a diesel migration:
+//!
+//! ```ignore
+//! rdiesel::rls::Migration::new(UserSettings::new())
+//!     .table::<schema::wishes::table>()
+//!     .table::<schema::friendships::table>()
+//!     .write("migrations/2024-01-01-000000_row_level_security")?;
+//! ```
+//!
+//! Policies are translated as follows:
+//!
+//! * `row.field` is the column of the field.
+//! * `user.field` is the setting `app.user_field` cast to `integer`, see [`UserSettings`]. The
+//!   application must set it on every connection it uses for the user, e.g., with `pg::set_user`
+//!   from [`ContextImpl::on_enter`](crate::ContextImpl::on_enter). While it is not set, the
+//!   field is `NULL` and the policies deny every row.
+//! * Calls of uninterpreted functions, such as `friends(row.owner, user.id)`, are calls of SQL
+//!   functions of the same name, which must exist in the database.
+//! * Rust constants and literals are SQL literals, see [`SqlLiteral`].
+//!
+//! Parts of a policy that cannot be translated are replaced by `FALSE`, or by `TRUE` under a
+//! negation, so the database never admits a row that the policy does not. A row can be read if
+//! the table's `read` clause or that of any of its columns allows it, as `select_columns` needs
+//! to see the row, and updated if any of its columns can. Parts of column policies that mention
+//! the assigned value are approximated in the same way. Row invariants are not translated; they are better enforced with
+//! `CHECK` constraints.
+//!
+//! [`TablePolicies::up_sql`] also forces row-level security, so the policies apply to the owner
+//! of the table, which is often the role the application connects as. Only superusers and roles
+//! with `BYPASSRLS` are exempt. Statements that must see every row, such as later migrations
+//! that backfill or rewrite data, therefore have to run as such a role: run as the owner without
+//! a user being set, they see and change no rows at all. Constraint checks such as foreign keys
+//! are not affected.
+use std::{collections::HashMap, fmt::Write as _, fs, io, path::Path};
+
+use diesel::{
+    pg::{Pg, PgQueryBuilder},
+    query_builder::{QueryBuilder, QueryFragment},
+};
+
+/// How the fields of the user are read from the database session.
+#[derive(Clone, Debug)]
+pub struct UserSettings {
+    prefix: String,
+    types: HashMap<String, String>,
+}
+
+impl UserSettings {
+    /// Reads `user.field` from the setting `app.user_field` as an `integer`.
+    pub fn new() -> Self {
+        Self {
+            prefix: "app.user_".to_string(),
+            types: HashMap::new(),
+        }
+    }
+
+    /// Sets the prefix that names the setting of a field.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Sets the SQL type of `field`, which is `integer` by default.
+    pub fn field_type(mut self, field: impl Into<String>, sql_type: impl Into<String>) -> Self {
+        self.types.insert(field.into(), sql_type.into());
+        self
+    }
+
+    /// The name of the setting of `field`.
+    pub fn setting(&self, field: &str) -> String {
+        format!("{}{field}", self.prefix)
+    }
+
+    /// The SQL expression that reads `field`. It is `NULL` if the setting is missing or was
+    /// cleared by `pg::reset_user`, so that policies deny every row instead of raising an error.
+    pub fn field(&self, field: &str) -> String {
+        let sql_type = self.types.get(field).map_or("integer", String::as_str);
+        format!(
+            "NULLIF(current_setting('{}', true), '')::{sql_type}",
+            self.setting(field)
+        )
+    }
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The translated policies of a table. `None` means that no rows are allowed, which is what
+/// PostgreSQL assumes for operations without a policy.
+#[derive(Clone, Debug)]
+pub struct TablePolicies {
+    /// The quoted and possibly schema-qualified name of the table, see [`table_name`].
+    pub table: String,
+    pub select: Option<String>,
+    pub insert: Option<String>,
+    pub update: Option<String>,
+    pub delete: Option<String>,
+}
+
+impl TablePolicies {
+    /// The statements that enable row-level security on the table and create its policies.
+    pub fn up_sql(&self) -> String {
+        let table = &self.table;
+        let mut sql = String::new();
+        writeln!(sql, "ALTER TABLE {table} ENABLE ROW LEVEL SECURITY;").unwrap();
+        writeln!(sql, "ALTER TABLE {table} FORCE ROW LEVEL SECURITY;").unwrap();
+        for (name, command, clause, policy) in self.policies() {
+            if let Some(policy) = policy {
+                writeln!(
+                    sql,
+                    "CREATE POLICY {name} ON {table} FOR {command} {clause} ({policy});"
+                )
+                .unwrap();
+            }
+        }
+        sql
+    }
+
+    /// The statements that undo [`TablePolicies::up_sql`].
+    pub fn down_sql(&self) -> String {
+        let table = &self.table;
+        let mut sql = String::new();
+        for (name, _, _, policy) in self.policies() {
+            if policy.is_some() {
+                writeln!(sql, "DROP POLICY IF EXISTS {name} ON {table};").unwrap();
+            }
+        }
+        writeln!(sql, "ALTER TABLE {table} NO FORCE ROW LEVEL SECURITY;").unwrap();
+        writeln!(sql, "ALTER TABLE {table} DISABLE ROW LEVEL SECURITY;").unwrap();
+        sql
+    }
+
+    fn policies(&self) -> [(&str, &str, &str, &Option<String>); 4] {
+        [
+            ("rdiesel_select", "SELECT", "USING", &self.select),
+            ("rdiesel_insert", "INSERT", "WITH CHECK", &self.insert),
+            ("rdiesel_update", "UPDATE", "USING", &self.update),
+            ("rdiesel_delete", "DELETE", "USING", &self.delete),
+        ]
+    }
+}
+
+/// A table whose policies can be translated to row-level security policies. Implemented by
+/// [`policy!`](crate::policy).
+pub trait RowLevelSecurity {
+    fn row_level_security(user: &UserSettings) -> TablePolicies;
+}
+
+/// The name of `table` as diesel writes it in PostgreSQL queries, i.e., its SQL name, which may
+/// differ from the name of its Rust module, quoted and qualified with its schema, if any.
+pub fn table_name<T: QueryFragment<Pg>>(table: &T) -> String {
+    let mut out = PgQueryBuilder::new();
+    table
+        .to_sql(&mut out, &Pg)
+        .expect("table names are plain identifiers");
+    out.finish()
+}
+
+/// A Rust value that can be inlined into a policy as a SQL literal.
+pub trait SqlLiteral {
+    fn to_sql_literal(&self) -> String;
+}
+
+macro_rules! display_literal {
+    ($($ty:ty),*) => {
+        $(
+            impl SqlLiteral for $ty {
+                fn to_sql_literal(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+display_literal!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+impl SqlLiteral for bool {
+    fn to_sql_literal(&self) -> String {
+        if *self { "TRUE" } else { "FALSE" }.to_string()
+    }
+}
+
+impl SqlLiteral for str {
+    fn to_sql_literal(&self) -> String {
+        format!("'{}'", self.replace('\'', "''"))
+    }
+}
+
+impl SqlLiteral for String {
+    fn to_sql_literal(&self) -> String {
+        self.as_str().to_sql_literal()
+    }
+}
+
+impl<T: SqlLiteral + ?Sized> SqlLiteral for &T {
+    fn to_sql_literal(&self) -> String {
+        (**self).to_sql_literal()
+    }
+}
+
+/// The row-level security policies of a set of tables, rendered as a diesel migration.
+#[derive(Clone, Debug)]
+pub struct Migration {
+    user: UserSettings,
+    tables: Vec<TablePolicies>,
+}
+
+impl Migration {
+    pub fn new(user: UserSettings) -> Self {
+        Self {
+            user,
+            tables: vec![],
+        }
+    }
+
+    pub fn table<T: RowLevelSecurity>(mut self) -> Self {
+        self.tables.push(T::row_level_security(&self.user));
+        self
+    }
+
+    pub fn up_sql(&self) -> String {
+        self.tables
+            .iter()
+            .map(TablePolicies::up_sql)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn down_sql(&self) -> String {
+        self.tables
+            .iter()
+            .rev()
+            .map(TablePolicies::down_sql)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Writes `up.sql` and `down.sql` to the migration directory `dir`, creating it if needed.
+    pub fn write(&self, dir: impl AsRef<Path>) -> io::Result<()> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join("up.sql"), self.up_sql())?;
+        fs::write(dir.join("down.sql"), self.down_sql())
+    }
+}
@@ -0,0 +1,178 @@
+//! Rocket integration, enabled by the `rocket` feature.
+//!
+//! [`DbContext`] is a request guard that checks out a connection from a `rocket_sync_db_pools`
+//! pool and authenticates the user of the request with an [`Authenticator`]. Requests that are
+//! not authenticated fail with [`Status::Unauthorized`]. The authenticator can also prepare the
+//! connection for the user, e.g., for row-level security.
+//!
+//! Pooled connections are only available on a blocking thread, so a [`DbContext`] gives out the
+//! [`Context`] of the user in [`DbContext::run`]:
+//!
+//! ```ignore
+//! #[database("wishlist")]
+//! struct Db(diesel::PgConnection);
+//!
+//! struct CookieAuth;
+//!
+//! #[rocket::async_trait]
+//! impl Authenticator<Db, diesel::PgConnection> for CookieAuth {
+//!     type User = User;
+//!
+//!     async fn authenticate(req: &Request<'_>, conn: &Connection<Db, PgConnection>) -> Option<User> {
+//!         let id: i32 = req.cookies().get_private("user_id")?.value().parse().ok()?;
+//!         conn.run(move |conn| users::table.find(id).first(conn).ok()).await
+//!     }
+//!
+//!     fn on_enter(conn: &mut PgConnection, user: &User) -> QueryResult<()> {
+//!         pg::set_user(conn, &UserSettings::new(), user)
+//!     }
+//!
+//!     fn on_exit(conn: &mut PgConnection, user: &User) -> QueryResult<()> {
+//!         pg::reset_user(conn, &UserSettings::new(), user)
+//!     }
+//! }
+//!
+//! #[rocket::get("/wishes")]
+//! async fn wishes(cx: DbContext<Db, PgConnection, CookieAuth>) -> Option<Json<Vec<Wish>>> {
+//!     cx.run(|cx| {
+//!         let user = cx.auth_user();
+//!         cx.select_list(wishes::owner.eq(user.id))
+//!     })
+//!     .await
+//!     .ok()
+//!     .map(Json)
+//! }
+//! ```
+use std::marker::PhantomData;
+
+use ::rocket::{
+    http::Status,
+    outcome::try_outcome,
+    request::{FromRequest, Outcome, Request},
+};
+use diesel::QueryResult;
+use rocket_sync_db_pools::{Connection, Poolable};
+
+use crate::{Context, ContextImpl};
+
+/// Resolves the user of a request.
+#[::rocket::async_trait]
+pub trait Authenticator<K, C: Poolable>: 'static {
+    type User: Clone + Send + Sync + 'static;
+
+    /// Returns the user of `req`, or `None` if the request is not authenticated. `conn` is the
+    /// connection of the request, e.g., to look up the user.
+    async fn authenticate(req: &Request<'_>, conn: &Connection<K, C>) -> Option<Self::User>;
+
+    /// Prepares the connection for `user` before [`DbContext::run`] hands out its context, see
+    /// [`ContextImpl::on_enter`]. For PostgreSQL row-level security, call
+    /// [`pg::set_user`](crate::pg::set_user) here and [`pg::reset_user`](crate::pg::reset_user)
+    /// in [`Authenticator::on_exit`].
+    fn on_enter(_conn: &mut C, _user: &Self::User) -> QueryResult<()> {
+        Ok(())
+    }
+
+    /// Undoes [`Authenticator::on_enter`] when the context is dropped, see
+    /// [`ContextImpl::on_exit`].
+    fn on_exit(_conn: &mut C, _user: &Self::User) -> QueryResult<()> {
+        Ok(())
+    }
+}
+
+/// A pooled connection and the authenticated user of a request, see the [module
+/// documentation](self).
+pub struct DbContext<K, C: Poolable, A: Authenticator<K, C>> {
+    conn: Connection<K, C>,
+    user: A::User,
+    _auth: PhantomData<fn() -> A>,
+}
+
+/// The [`ContextImpl`] of the contexts handed out by [`DbContext::run`], which is prepared with
+/// the hooks of the [`Authenticator`].
+pub struct RocketSession<'a, C, U> {
+    conn: &'a mut C,
+    user: U,
+    on_enter: fn(&mut C, &U) -> QueryResult<()>,
+    on_exit: fn(&mut C, &U) -> QueryResult<()>,
+}
+
+impl<C, U: Clone> ContextImpl for RocketSession<'_, C, U> {
+    type User = U;
+    type Conn = C;
+
+    fn auth_user(&self) -> U {
+        self.user.clone()
+    }
+
+    fn conn(&mut self) -> &mut C {
+        self.conn
+    }
+
+    fn on_enter(&mut self) -> QueryResult<()> {
+        (self.on_enter)(self.conn, &self.user)
+    }
+
+    fn on_exit(&mut self) -> QueryResult<()> {
+        (self.on_exit)(self.conn, &self.user)
+    }
+}
+
+#[flux_rs::ignore]
+const _: () = {
+    impl<K, C, A> DbContext<K, C, A>
+    where
+        K: 'static,
+        C: Poolable,
+        A: Authenticator<K, C>,
+    {
+        pub fn auth_user(&self) -> &A::User {
+            &self.user
+        }
+
+        /// Runs `f` with the context of the user on a blocking thread.
+        pub async fn run<F, R>(&self, f: F) -> QueryResult<R>
+        where
+            F: for<'a> FnOnce(
+                    &mut Context<RocketSession<'a, C, A::User>, A::User>,
+                ) -> QueryResult<R>
+                + Send
+                + 'static,
+            R: Send + 'static,
+        {
+            let user = self.user.clone();
+            self.conn
+                .run(move |conn| {
+                    let mut cx = Context::new(RocketSession {
+                        conn,
+                        user,
+                        on_enter: A::on_enter,
+                        on_exit: A::on_exit,
+                    })?;
+                    f(&mut cx)
+                })
+                .await
+        }
+    }
+
+    #[::rocket::async_trait]
+    impl<'r, K, C, A> FromRequest<'r> for DbContext<K, C, A>
+    where
+        K: 'static,
+        C: Poolable,
+        A: Authenticator<K, C>,
+    {
+        type Error = ();
+
+        async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+            let conn = try_outcome!(req.guard::<Connection<K, C>>().await);
+            match A::authenticate(req, &conn).await {
+                Some(user) => Outcome::Success(DbContext {
+                    conn,
+                    user,
+                    _auth: PhantomData,
+                }),
+                None => Outcome::Error((Status::Unauthorized, ())),
+            }
+        }
+    }
+};
@@ -0,0 +1,320 @@
+//! Runtime enforcement of policies, enabled by the `runtime-checks` feature.
+//!
+//! Flux checks policies statically and the methods of [`Context`] trust it. Builds that do not
+//! run Flux can enable `runtime-checks` to also evaluate the policies when a query is executed.
+//! [`policy!`] and `#[derive(RdieselModel)]` then compile each policy to a Rust predicate, i.e.,
+//! an impl of [`RowPolicy`] or [`FieldPolicy`], and the methods of [`Context`] check them:
+//!
+//! * `select_list` and `select_first` load the rows and check `read` on each of them.
+//! * `select_columns` first loads the full rows and checks the columns' `read`.
+//! * `insert` checks `insert` and the row invariant before executing.
+//! * `update_where` and `delete_where` re-read the affected rows and check `update` and the
+//!   columns' invariants, respectively `delete`, on each of them before executing.
+//!   `update_where` denies a changeset that assigns more than one column mentioned by the row
+//!   invariant without reading any row, see [`Field::in_row_invariant`].
+//!
+//! `select_list` and `select_first` also conjoin the row type's [`ReadFilter`] into the query,
+//! so rows that the user cannot read do not leave the database in the first place. Likewise,
+//! `update_where` and `delete_where` conjoin the [`ChangesetFilter`] of the changeset,
+//! respectively the row type's [`DeleteFilter`], into the `WHERE` clause of the write. Rows that
+//! another transaction commits between the check and the write are thus only written if the
+//! policies allow it, without relying on the isolation level or on row locks, which SQLite
+//! lacks. Such rows are skipped rather than reported, and so are rows whose policies the filters
+//! cannot express, see [`ReadFilter`].
+//!
+//! Reads and writes that check affected rows run in a transaction. If a check fails, the query
+//! is not executed and the method returns a [`PolicyViolation`] wrapped in
+//! [`diesel::result::Error::QueryBuilderError`], see [`PolicyViolation::from_error`].
+//!
+//! A policy is compiled by evaluating it as a Rust expression in which `user`, `row` and `val`
+//! are references to the user, the row and the assigned value. This covers comparisons, boolean
+//! connectives, constants and field accesses, provided that the refinement indices of the row
+//! type are named after its fields. Uninterpreted functions must have a Rust counterpart in
+//! scope that takes the same arguments.
+//!
+//! [`Context`]: crate::Context
+//! [`Field::in_row_invariant`]: crate::Field::in_row_invariant
+//! [`policy!`]: crate::policy
+use std::fmt;
+
+use diesel::QueryResult;
+
+use crate::{And, Assign};
+
+/// The operation that was denied by a [`PolicyViolation`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Read,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Read => f.write_str("read"),
+            Operation::Insert => f.write_str("insert"),
+            Operation::Update => f.write_str("update"),
+            Operation::Delete => f.write_str("delete"),
+        }
+    }
+}
+
+/// A query was not executed because it would have violated a policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PolicyViolation {
+    operation: Operation,
+}
+
+impl PolicyViolation {
+    pub fn new(operation: Operation) -> Self {
+        Self { operation }
+    }
+
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// Returns the violation `err` wraps, if any.
+    pub fn from_error(err: &diesel::result::Error) -> Option<&PolicyViolation> {
+        match err {
+            diesel::result::Error::QueryBuilderError(err) => err.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} denied by policy", self.operation)
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+impl From<PolicyViolation> for diesel::result::Error {
+    fn from(violation: PolicyViolation) -> Self {
+        diesel::result::Error::QueryBuilderError(Box::new(violation))
+    }
+}
+
+/// The runtime counterpart of [`Row`](crate::Row). Missing policies deny.
+pub trait RowPolicy<U> {
+    fn allow_insert(&self, user: &U) -> bool;
+
+    fn allow_read(&self, _user: &U) -> bool {
+        false
+    }
+
+    fn allow_delete(&self, _user: &U) -> bool {
+        false
+    }
+
+    fn invariant(&self) -> bool {
+        true
+    }
+}
+
+/// The read policy of a row type as an [`Expr`](crate::Expr), which is conjoined into the
+/// `WHERE` clause of `select_list` and `select_first`.
+///
+/// [`policy!`](crate::policy) and `#[derive(RdieselModel)]` translate the `read` policy as far as
+/// the `Expr` DSL goes and approximate the rest conservatively, e.g., a policy that depends on an
+/// uninterpreted function of the row may filter out rows that it allows. Rows are still checked
+/// against [`RowPolicy::allow_read`] after they have been loaded.
+pub trait ReadFilter<U> {
+    type Filter;
+
+    fn read_filter(user: &U) -> Self::Filter;
+}
+
+/// The delete policy of a row type as an [`Expr`](crate::Expr), which is conjoined into the
+/// `WHERE` clause of `delete_where`. It is translated like [`ReadFilter`], so a row that changes
+/// between the check and the `DELETE` is only deleted if the policy still allows it.
+pub trait DeleteFilter<U> {
+    type Filter;
+
+    fn delete_filter(user: &U) -> Self::Filter;
+}
+
+/// The type of the values of a column of the row type `R`, emitted by
+/// `#[derive(RdieselModel)]` to type [`ReadFilter`]s.
+pub trait ColumnValue<R> {
+    type Value;
+}
+
+/// The runtime counterpart of [`Field`](crate::Field). Missing policies deny.
+pub trait FieldPolicy<R, U, V> {
+    fn allow_update(user: &U, row: &R, val: &V) -> bool;
+
+    fn allow_read(_user: &U, _row: &R) -> bool {
+        false
+    }
+
+    fn invariant(_row: &R, _val: &V) -> bool {
+        false
+    }
+
+    fn in_row_invariant() -> bool {
+        true
+    }
+}
+
+/// The runtime counterpart of [`Changeset`](crate::Changeset).
+pub trait ChangesetPolicy<R, U> {
+    fn allow_update(&self, user: &U, row: &R) -> bool;
+
+    fn invariant(&self, row: &R) -> bool;
+
+    fn row_invariant_columns(&self) -> usize;
+}
+
+impl<F, V, R, U> ChangesetPolicy<R, U> for Assign<F, V>
+where
+    F: FieldPolicy<R, U, V>,
+{
+    fn allow_update(&self, user: &U, row: &R) -> bool {
+        F::allow_update(user, row, &self.val)
+    }
+
+    fn invariant(&self, row: &R) -> bool {
+        F::invariant(row, &self.val)
+    }
+
+    fn row_invariant_columns(&self) -> usize {
+        usize::from(F::in_row_invariant())
+    }
+}
+
+impl<A, B, R, U> ChangesetPolicy<R, U> for (A, B)
+where
+    A: ChangesetPolicy<R, U>,
+    B: ChangesetPolicy<R, U>,
+{
+    fn allow_update(&self, user: &U, row: &R) -> bool {
+        self.0.allow_update(user, row) && self.1.allow_update(user, row)
+    }
+
+    fn invariant(&self, row: &R) -> bool {
+        self.0.invariant(row) && self.1.invariant(row) && self.row_invariant_columns() <= 1
+    }
+
+    fn row_invariant_columns(&self) -> usize {
+        self.0.row_invariant_columns() + self.1.row_invariant_columns()
+    }
+}
+
+/// The update policy and invariant of a column as an [`Expr`](crate::Expr) over the rows in which
+/// the column may be assigned `val`, translated like [`ReadFilter`].
+pub trait UpdateFilter<R, U, V> {
+    type Filter;
+
+    fn update_filter(user: &U, val: &V) -> Self::Filter;
+}
+
+/// The conjunction of the [`UpdateFilter`]s of the assignments of a changeset, which is conjoined
+/// into the `WHERE` clause of `update_where`.
+pub trait ChangesetFilter<R, U> {
+    type Filter;
+
+    fn update_filter(&self, user: &U) -> Self::Filter;
+}
+
+impl<F, V, R, U> ChangesetFilter<R, U> for Assign<F, V>
+where
+    F: UpdateFilter<R, U, V>,
+{
+    type Filter = F::Filter;
+
+    fn update_filter(&self, user: &U) -> F::Filter {
+        F::update_filter(user, &self.val)
+    }
+}
+
+impl<A, B, R, U> ChangesetFilter<R, U> for (A, B)
+where
+    A: ChangesetFilter<R, U>,
+    B: ChangesetFilter<R, U>,
+{
+    type Filter = And<A::Filter, B::Filter>;
+
+    fn update_filter(&self, user: &U) -> Self::Filter {
+        And {
+            lhs: self.0.update_filter(user),
+            rhs: self.1.update_filter(user),
+        }
+    }
+}
+
+/// The runtime counterpart of [`Projection`](crate::Projection).
+pub trait ProjectionPolicy<R, U, V> {
+    fn allow_read(user: &U, row: &R) -> bool;
+}
+
+macro_rules! impl_projection_policy {
+    () => {};
+    ($col:ident $val:ident $(, $cols:ident $vals:ident)*) => {
+        impl<R, U, $col, $val, $($cols, $vals),*> ProjectionPolicy<R, U, ($val, $($vals,)*)>
+            for ($col, $($cols,)*)
+        where
+            $col: FieldPolicy<R, U, $val>,
+            $($cols: FieldPolicy<R, U, $vals>,)*
+        {
+            fn allow_read(user: &U, row: &R) -> bool {
+                $col::allow_read(user, row) $(&& $cols::allow_read(user, row))*
+            }
+        }
+
+        impl_projection_policy!($($cols $vals),*);
+    };
+}
+
+// The same arities as `Projection`.
+impl_projection_policy!(
+    C0 V0, C1 V1, C2 V2, C3 V3, C4 V4, C5 V5, C6 V6, C7 V7,
+    C8 V8, C9 V9, C10 V10, C11 V11, C12 V12, C13 V13, C14 V14, C15 V15,
+    C16 V16, C17 V17, C18 V18, C19 V19, C20 V20, C21 V21, C22 V22, C23 V23,
+    C24 V24, C25 V25, C26 V26, C27 V27, C28 V28, C29 V29, C30 V30, C31 V31
+);
+
+/// Fails with a [`PolicyViolation`] unless `allow` holds for every row.
+pub(crate) fn check<'a, R: 'a>(
+    rows: impl IntoIterator<Item = &'a R>,
+    operation: Operation,
+    allow: impl FnMut(&R) -> bool,
+) -> QueryResult<()> {
+    if rows.into_iter().all(allow) {
+        Ok(())
+    } else {
+        Err(PolicyViolation::new(operation).into())
+    }
+}
+
+/// Conjoins the read filter of `R` into `q`.
+pub(crate) fn read_filtered<R: ReadFilter<U>, U, Q>(user: &U, q: Q) -> And<Q, R::Filter> {
+    And {
+        lhs: q,
+        rhs: R::read_filter(user),
+    }
+}
+
+/// Conjoins the update filter of the changeset `v` into `q`.
+pub(crate) fn update_filtered<R, U, Q, C: ChangesetFilter<R, U>>(
+    user: &U,
+    q: Q,
+    v: &C,
+) -> And<Q, C::Filter> {
+    And {
+        lhs: q,
+        rhs: v.update_filter(user),
+    }
+}
+
+/// Conjoins the delete filter of `R` into `q`.
+pub(crate) fn delete_filtered<R: DeleteFilter<U>, U, Q>(user: &U, q: Q) -> And<Q, R::Filter> {
+    And {
+        lhs: q,
+        rhs: R::delete_filter(user),
+    }
+}
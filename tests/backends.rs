@@ -0,0 +1,112 @@
+//! What the bridge does differently on backends other than PostgreSQL: boolean literals, which
+//! are bound as parameters, and the SQL generated for MySQL, which needs a server to run. The
+//! operations themselves are tested end to end on SQLite in `context.rs`.
+mod common;
+
+use common::{context, establish, models::*, schema::wishes, where_clause, PRIVATE, PUBLIC};
+use diesel::{mysql::Mysql, sqlite::Sqlite};
+use rdiesel::{debug, Expr, Field};
+
+/// The read policy of [`Wish`] as rendered by the guards under `runtime-checks`.
+const READ: &str = "((`wishes`.`owner` = ?) OR (`wishes`.`access_level` = ?))";
+
+#[test]
+fn sqlite_bool_literal() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let none: Vec<Wish> = cx.select_list(false).unwrap();
+    let all: Vec<Wish> = cx.select_list(wishes::owner.eq(user.id).and(true)).unwrap();
+
+    assert!(none.is_empty());
+    assert_eq!(all.len(), 2);
+}
+
+#[test]
+fn mysql_select_list() {
+    let user = User { id: 1 };
+    let sql = debug::select_list::<Wish, Mysql, _, _>(
+        &user,
+        wishes::owner
+            .eq(1)
+            .and(wishes::price.lt(100).or(wishes::access_level.eq(PUBLIC))),
+    );
+
+    let clause = where_clause(
+        "((`wishes`.`owner` = ?) AND ((`wishes`.`price` < ?) OR (`wishes`.`access_level` = ?)))",
+        "1, 100, 0",
+        READ,
+        "1, 0",
+    );
+    assert_eq!(
+        sql,
+        format!(
+            "SELECT `wishes`.`id`, `wishes`.`owner`, `wishes`.`title`, `wishes`.`price`, \
+             `wishes`.`access_level`, `wishes`.`discount` FROM `wishes` {clause}"
+        ),
+    );
+}
+
+#[test]
+fn mysql_update_where() {
+    let user = User { id: 1 };
+    let sql = debug::update_where::<Wish, Mysql, _, _, _>(
+        &user,
+        wishes::owner.eq(user.id).and(wishes::id.eq(2)),
+        wishes::price.assign(25),
+    );
+
+    let clause = where_clause(
+        "((`wishes`.`owner` = ?) AND (`wishes`.`id` = ?))",
+        "25, 1, 2",
+        // The invariant on the new price does not mention the row and is bound as a literal.
+        "((`wishes`.`owner` = ?) AND ?)",
+        "1, true",
+    );
+    assert_eq!(sql, format!("UPDATE `wishes` SET `price` = ? {clause}"));
+}
+
+#[test]
+fn mysql_insert() {
+    let sql = debug::insert::<NewWish, Mysql>(NewWish {
+        owner: 2,
+        title: "lamp".to_string(),
+        price: 40,
+        access_level: PRIVATE,
+    });
+
+    assert_eq!(
+        sql,
+        "INSERT INTO `wishes` (`owner`, `title`, `price`, `access_level`) VALUES (?, ?, ?, ?) \
+         -- binds: [2, \"lamp\", 40, 1]",
+    );
+}
+
+#[test]
+fn mysql_delete_where() {
+    let user = User { id: 1 };
+    let sql = debug::delete_where::<Wish, Mysql, _, _>(&user, wishes::id.eq(2));
+
+    let clause = where_clause("(`wishes`.`id` = ?)", "2", "(`wishes`.`owner` = ?)", "1");
+    // Diesel renders deletes with two spaces after `DELETE`.
+    assert_eq!(sql, format!("DELETE  FROM `wishes` {clause}"));
+}
+
+#[test]
+fn bool_literals_are_bound() {
+    let user = User { id: 1 };
+    let sqlite = debug::select_list::<Wish, Sqlite, _, _>(&user, true);
+    let mysql = debug::select_list::<Wish, Mysql, _, _>(&user, false);
+
+    let sqlite_clause = where_clause("?", "true", READ, "1, 0");
+    let mysql_clause = where_clause("?", "false", READ, "1, 0");
+    assert!(
+        sqlite.ends_with(&format!("FROM `wishes` {sqlite_clause}")),
+        "{sqlite}"
+    );
+    assert!(
+        mysql.ends_with(&format!("FROM `wishes` {mysql_clause}")),
+        "{mysql}"
+    );
+}
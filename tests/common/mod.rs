@@ -0,0 +1,205 @@
+//! The schema, models and policies shared by the integration tests, on an in-memory SQLite
+//! database.
+#![allow(dead_code)]
+
+use diesel::{Connection, RunQueryDsl, SqliteConnection};
+use flux_rs::*;
+use rdiesel::ContextImpl;
+
+use self::models::{User, Wish};
+
+#[constant]
+pub const PUBLIC: i32 = 0;
+#[constant]
+pub const PRIVATE: i32 = 1;
+
+#[trusted]
+pub mod schema {
+    diesel::table! {
+        wishes (id) {
+            id -> Integer,
+            owner -> Integer,
+            title -> Text,
+            price -> Integer,
+            access_level -> Integer,
+            discount -> Nullable<Integer>,
+        }
+    }
+}
+
+pub mod models {
+    use diesel::{associations::Identifiable, Insertable, Queryable, Selectable};
+    use flux_rs::*;
+    use rdiesel::RdieselModel;
+
+    // Only compiled to Rust by the policies with the `runtime-checks` feature.
+    #[allow(unused_imports)]
+    use super::{PRIVATE, PUBLIC};
+
+    flux!(
+    #[derive(Clone, Debug)]
+    pub struct User[id: int] {
+        pub id: i32[id],
+    }
+
+    #[derive(Clone, Debug, PartialEq, Queryable, Selectable, Identifiable, RdieselModel)]
+    #[diesel(table_name = super::schema::wishes)]
+    pub struct Wish[id: int, owner: int, price: int, access_level: int] {
+        pub id: i32[id],
+        pub owner: i32[owner],
+        pub title: String,
+        pub price: i32[price],
+        pub access_level: i32[access_level],
+        pub discount: Option<i32>,
+    }
+
+    #[derive(Clone, Insertable)]
+    #[diesel(table_name = super::schema::wishes)]
+    pub struct NewWish[owner: int, price: int, access_level: int] {
+        pub owner: i32[owner],
+        pub title: String,
+        pub price: i32[price],
+        pub access_level: i32[access_level],
+    }
+    );
+
+    rdiesel::policy! {
+        table super::schema::wishes(Wish) for User {
+            read: user.id == row.owner || row.access_level == PUBLIC,
+            read(id: i32, title: String): true,
+            insert(NewWish): user.id == row.owner,
+            update(id: i32, owner: i32, access_level: i32): false,
+            update(title: String, price: i32, discount: Option<i32>): user.id == row.owner,
+            delete: user.id == row.owner,
+            invariant: row.price >= 0 && (row.access_level == PUBLIC || row.access_level == PRIVATE),
+            invariant(price: i32): val >= 0,
+        }
+    }
+
+    rdiesel::assert_policy_coverage!(super::schema::wishes::table, Wish, User);
+
+    impl diesel::associations::HasTable for NewWish {
+        type Table = super::schema::wishes::table;
+
+        fn table() -> Self::Table {
+            super::schema::wishes::table
+        }
+    }
+}
+
+/// A [`ContextImpl`] over a borrowed connection, so that tests can inspect the database once
+/// the context is dropped.
+pub struct SqliteSession<'a> {
+    conn: &'a mut SqliteConnection,
+    user: User,
+}
+
+pub type Context<'a> = rdiesel::Context<SqliteSession<'a>, User>;
+
+impl ContextImpl for SqliteSession<'_> {
+    type User = User;
+    type Conn = SqliteConnection;
+
+    fn auth_user(&self) -> User {
+        self.user.clone()
+    }
+
+    fn conn(&mut self) -> &mut SqliteConnection {
+        self.conn
+    }
+}
+
+/// The rows every test starts from: user 1 owns a public and a private wish, user 2 a public
+/// one without a discount.
+pub fn seed() -> Vec<Wish> {
+    vec![
+        wish(1, 1, "bike", 300, PUBLIC, Some(10)),
+        wish(2, 1, "kite", 20, PRIVATE, Some(50)),
+        wish(3, 2, "book", 15, PUBLIC, None),
+    ]
+}
+
+pub fn wish(
+    id: i32,
+    owner: i32,
+    title: &str,
+    price: i32,
+    access_level: i32,
+    discount: Option<i32>,
+) -> Wish {
+    Wish {
+        id,
+        owner,
+        title: title.to_string(),
+        price,
+        access_level,
+        discount,
+    }
+}
+
+/// Opens a fresh in-memory database with the [`seed`] rows.
+#[flux_rs::ignore]
+pub fn establish() -> SqliteConnection {
+    let mut conn = SqliteConnection::establish(":memory:").expect("failed to open the database");
+    diesel::sql_query(
+        "CREATE TABLE wishes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            owner INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            price INTEGER NOT NULL,
+            access_level INTEGER NOT NULL,
+            discount INTEGER
+        )",
+    )
+    .execute(&mut conn)
+    .expect("failed to create the schema");
+    for w in seed() {
+        diesel::sql_query("INSERT INTO wishes VALUES (?, ?, ?, ?, ?, ?)")
+            .bind::<diesel::sql_types::Integer, _>(w.id)
+            .bind::<diesel::sql_types::Integer, _>(w.owner)
+            .bind::<diesel::sql_types::Text, _>(w.title)
+            .bind::<diesel::sql_types::Integer, _>(w.price)
+            .bind::<diesel::sql_types::Integer, _>(w.access_level)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Integer>, _>(w.discount)
+            .execute(&mut conn)
+            .expect("failed to seed the database");
+    }
+    conn
+}
+
+/// The `WHERE` clause, with its bind parameters, of a query filtered by `filter` bound to
+/// `binds`, which may be empty. Under `runtime-checks` the guards conjoin the policy, which
+/// renders as `policy` bound to `policy_binds`.
+pub fn where_clause(filter: &str, binds: &str, policy: &str, policy_binds: &str) -> String {
+    if cfg!(feature = "runtime-checks") {
+        let binds = [binds, policy_binds]
+            .into_iter()
+            .filter(|binds| !binds.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("WHERE ({filter} AND {policy}) -- binds: [{binds}]")
+    } else {
+        format!("WHERE {filter} -- binds: [{binds}]")
+    }
+}
+
+/// The context of user `id` on `conn`.
+pub fn context(conn: &mut SqliteConnection, id: i32) -> Context<'_> {
+    let session = SqliteSession {
+        conn,
+        user: User { id },
+    };
+    Context::new(session).expect("failed to prepare the connection")
+}
+
+/// All rows of the table ordered by id, bypassing the policies.
+#[flux_rs::ignore]
+pub fn rows(conn: &mut SqliteConnection) -> Vec<Wish> {
+    use diesel::{QueryDsl, SelectableHelper};
+
+    schema::wishes::table
+        .order(schema::wishes::id)
+        .select(Wish::as_select())
+        .load(conn)
+        .expect("failed to load the rows")
+}
@@ -0,0 +1,641 @@
+//! Every operation of [`rdiesel::Context`] on SQLite, checked against the SQL it generates and
+//! against the rows that `Expr::eval` promises, i.e., the seed rows filtered in Rust.
+mod common;
+
+use common::{
+    context, establish, models::*, rows, schema::wishes, seed, where_clause, wish, PRIVATE, PUBLIC,
+};
+use diesel::{sqlite::Sqlite, QueryResult, SqliteConnection};
+use rdiesel::{debug, ContextImpl, Expr, Field, NullableExpr, ValueSet};
+
+/// The seed rows that satisfy `f`, in id order.
+fn expected(f: impl Fn(&Wish) -> bool) -> Vec<Wish> {
+    seed().into_iter().filter(f).collect()
+}
+
+const COLUMNS: &str = "SELECT `wishes`.`id`, `wishes`.`owner`, `wishes`.`title`, \
+                       `wishes`.`price`, `wishes`.`access_level`, `wishes`.`discount` \
+                       FROM `wishes`";
+
+/// The SQL that `select_list` runs for `user` given a filter that renders as `filter` with
+/// `binds`.
+fn select_sql(user: &User, filter: &str, binds: &str) -> String {
+    let read = "((`wishes`.`owner` = ?) OR (`wishes`.`access_level` = ?))";
+    let clause = where_clause(filter, binds, read, &format!("{}, {PUBLIC}", user.id));
+    format!("{COLUMNS} {clause}")
+}
+
+#[test]
+fn select_list() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 2);
+
+    let user = cx.auth_user();
+    let filter = wishes::owner.eq(user.id);
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(sql, select_sql(&user, "(`wishes`.`owner` = ?)", "2"),);
+    assert_eq!(found, expected(|w| w.owner == 2));
+}
+
+#[test]
+fn select_list_and_of_or() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = wishes::owner
+        .eq(user.id)
+        .and(wishes::price.lt(100).or(wishes::access_level.eq(PRIVATE)));
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(
+            &user,
+            "((`wishes`.`owner` = ?) AND ((`wishes`.`price` < ?) OR \
+             (`wishes`.`access_level` = ?)))",
+            "1, 100, 1",
+        ),
+    );
+    assert_eq!(
+        found,
+        expected(|w| w.owner == 1 && (w.price < 100 || w.access_level == PRIVATE)),
+    );
+}
+
+#[test]
+fn select_list_or_of_and() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 2);
+
+    let user = cx.auth_user();
+    let filter = wishes::owner
+        .eq(user.id)
+        .or(wishes::access_level.eq(PUBLIC).and(wishes::price.gt(100)));
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(
+            &user,
+            "((`wishes`.`owner` = ?) OR ((`wishes`.`access_level` = ?) AND \
+             (`wishes`.`price` > ?)))",
+            "2, 0, 100",
+        ),
+    );
+    assert_eq!(
+        found,
+        expected(|w| w.owner == 2 || (w.access_level == PUBLIC && w.price > 100)),
+    );
+}
+
+#[test]
+fn select_list_eq_any() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 2);
+
+    let user = cx.auth_user();
+    let mut ids = ValueSet::new();
+    ids.insert(1);
+    ids.insert(2);
+    ids.insert(3);
+    let filter = wishes::id.eq_any(ids).and(wishes::access_level.eq(PUBLIC));
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(
+            &user,
+            "((`wishes`.`id` IN (?, ?, ?)) AND (`wishes`.`access_level` = ?))",
+            "1, 2, 3, 0",
+        ),
+    );
+    assert_eq!(
+        found,
+        expected(|w| [1, 2, 3].contains(&w.id) && w.access_level == PUBLIC),
+    );
+}
+
+#[test]
+fn select_list_eq_any_empty() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let found: Vec<Wish> = cx.select_list(wishes::id.eq_any(ValueSet::new())).unwrap();
+
+    assert!(found.is_empty());
+}
+
+#[test]
+fn select_list_like() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = wishes::title.like("b%".to_string());
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(&user, "(`wishes`.`title` LIKE ?)", "\"b%\"")
+    );
+    assert_eq!(found, expected(|w| w.title.starts_with('b')));
+}
+
+#[test]
+fn select_list_not_like() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = wishes::title.not_like("%i%".to_string());
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(&user, "(`wishes`.`title` NOT LIKE ?)", "\"%i%\"")
+    );
+    assert_eq!(found, expected(|w| !w.title.contains('i')));
+}
+
+#[test]
+fn select_list_is_null() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, wishes::discount.is_null());
+    let found: Vec<Wish> = cx.select_list(wishes::discount.is_null()).unwrap();
+
+    assert_eq!(sql, select_sql(&user, "(`wishes`.`discount` IS NULL)", ""));
+    assert_eq!(found, expected(|w| w.discount.is_none()));
+}
+
+#[test]
+fn select_list_is_not_null() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, wishes::discount.is_not_null());
+    let found: Vec<Wish> = cx.select_list(wishes::discount.is_not_null()).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(&user, "(`wishes`.`discount` IS NOT NULL)", ""),
+    );
+    assert_eq!(found, expected(|w| w.discount.is_some()));
+}
+
+/// A comparison against `NULL` is `NULL`, so neither a comparison nor its negation returns the
+/// row without a discount, as documented by `NullableExpr`.
+#[test]
+fn select_list_nullable_comparison() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = wishes::discount.lt(30);
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Wish> = cx.select_list(filter.clone()).unwrap();
+    let negated: Vec<Wish> = cx.select_list(filter.not()).unwrap();
+    let either: Vec<Wish> = cx
+        .select_list(wishes::discount.lt(30).or(wishes::discount.is_null()))
+        .unwrap();
+
+    assert_eq!(sql, select_sql(&user, "(`wishes`.`discount` < ?)", "30"));
+    assert_eq!(found, expected(|w| w.discount.is_some_and(|d| d < 30)));
+    assert_eq!(negated, expected(|w| w.discount.is_some_and(|d| d >= 30)));
+    assert_eq!(either, expected(|w| w.discount.is_none_or(|d| d < 30)));
+}
+
+#[test]
+fn select_first() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let found: Option<Wish> = cx
+        .select_first(
+            wishes::owner
+                .eq(user.id)
+                .and(wishes::access_level.eq(PRIVATE)),
+        )
+        .unwrap();
+    let missing: Option<Wish> = cx
+        .select_first(wishes::owner.eq(user.id).and(wishes::price.lt(10)))
+        .unwrap();
+
+    assert_eq!(found, Some(wish(2, 1, "kite", 20, PRIVATE, Some(50))));
+    assert_eq!(missing, None);
+}
+
+#[test]
+fn select_columns() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let found: Vec<(i32, String)> = cx
+        .select_columns(wishes::price.gt(10), (wishes::id, wishes::title))
+        .unwrap();
+
+    let titles = expected(|w| w.price > 10)
+        .into_iter()
+        .map(|w| (w.id, w.title))
+        .collect::<Vec<_>>();
+    assert_eq!(found, titles);
+}
+
+#[test]
+fn update_where() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let updated = cx
+        .update_where(
+            wishes::owner.eq(user.id).and(wishes::price.lt(100)),
+            (
+                wishes::title.assign("yo-yo".to_string()),
+                wishes::price.assign(5),
+            ),
+        )
+        .unwrap();
+    drop(cx);
+
+    assert_eq!(updated, 1);
+    assert_eq!(
+        rows(&mut conn),
+        vec![
+            wish(1, 1, "bike", 300, PUBLIC, Some(10)),
+            wish(2, 1, "yo-yo", 5, PRIVATE, Some(50)),
+            wish(3, 2, "book", 15, PUBLIC, None),
+        ],
+    );
+}
+
+#[test]
+fn insert() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let inserted = cx
+        .insert(NewWish {
+            owner: user.id,
+            title: "drum".to_string(),
+            price: 80,
+            access_level: PUBLIC,
+        })
+        .unwrap();
+    drop(cx);
+
+    let mut all = seed();
+    all.push(wish(4, 1, "drum", 80, PUBLIC, None));
+    assert_eq!(inserted, 1);
+    assert_eq!(rows(&mut conn), all);
+}
+
+#[test]
+fn delete_where() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let deleted = cx
+        .delete_where(
+            wishes::owner
+                .eq(user.id)
+                .and(wishes::access_level.eq(PUBLIC)),
+        )
+        .unwrap();
+    drop(cx);
+
+    assert_eq!(deleted, 1);
+    assert_eq!(
+        rows(&mut conn),
+        expected(|w| !(w.owner == 1 && w.access_level == PUBLIC)),
+    );
+}
+
+#[test]
+fn transaction() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 2);
+
+    let user = cx.auth_user();
+    let rolled_back = cx.transaction(|cx| {
+        cx.insert(NewWish {
+            owner: user.id,
+            title: "pen".to_string(),
+            price: 3,
+            access_level: PRIVATE,
+        })?;
+        cx.delete_where(wishes::owner.eq(user.id))?;
+        Err::<(), _>(diesel::result::Error::RollbackTransaction)
+    });
+    let committed = cx.transaction(|cx| {
+        cx.insert(NewWish {
+            owner: user.id,
+            title: "pen".to_string(),
+            price: 3,
+            access_level: PRIVATE,
+        })
+    });
+    drop(cx);
+
+    let mut all = seed();
+    all.push(wish(4, 2, "pen", 3, PRIVATE, None));
+    assert_eq!(rolled_back, Err(diesel::result::Error::RollbackTransaction));
+    assert_eq!(committed, Ok(1));
+    assert_eq!(rows(&mut conn), all);
+}
+
+/// A session that logs its hooks, of which `on_enter` fails.
+struct FailingSession<'a> {
+    conn: &'a mut SqliteConnection,
+    hooks: &'a mut Vec<&'static str>,
+}
+
+impl ContextImpl for FailingSession<'_> {
+    type User = User;
+    type Conn = SqliteConnection;
+
+    fn auth_user(&self) -> User {
+        User { id: 1 }
+    }
+
+    fn conn(&mut self) -> &mut SqliteConnection {
+        self.conn
+    }
+
+    fn on_enter(&mut self) -> QueryResult<()> {
+        self.hooks.push("on_enter");
+        Err(diesel::result::Error::NotFound)
+    }
+
+    fn on_exit(&mut self) -> QueryResult<()> {
+        self.hooks.push("on_exit");
+        Ok(())
+    }
+}
+
+/// `on_exit` undoes whatever a failing `on_enter` did before the error.
+#[test]
+fn failed_enter_exits() {
+    let mut conn = establish();
+    let mut hooks = vec![];
+
+    let session = FailingSession {
+        conn: &mut conn,
+        hooks: &mut hooks,
+    };
+    let err = rdiesel::Context::new(session).err();
+
+    assert_eq!(err, Some(diesel::result::Error::NotFound));
+    assert_eq!(hooks, ["on_enter", "on_exit"]);
+}
+
+/// Operations that the policies deny fail with a [`PolicyViolation`] and leave the table as it
+/// was. Flux rejects them statically, so they only reach the database without it.
+///
+/// [`PolicyViolation`]: rdiesel::runtime::PolicyViolation
+#[cfg(feature = "runtime-checks")]
+mod denied {
+    use diesel::{result::Error, RunQueryDsl, SqliteConnection};
+    use flux_rs::*;
+    use rdiesel::{
+        interpreter::ExprRuntime,
+        runtime::{ChangesetFilter, DeleteFilter, Operation, PolicyViolation},
+        RdieselModel,
+    };
+
+    use super::*;
+
+    fn violation(err: &Error) -> Option<Operation> {
+        PolicyViolation::from_error(err).map(PolicyViolation::operation)
+    }
+
+    #[trusted]
+    mod schema {
+        diesel::table! {
+            spans (id) {
+                id -> Integer,
+                lo -> Integer,
+                hi -> Integer,
+            }
+        }
+    }
+
+    flux!(
+    #[derive(Clone, Debug, PartialEq, diesel::Queryable, diesel::Selectable, diesel::Identifiable, RdieselModel)]
+    #[diesel(table_name = schema::spans)]
+    struct Span[id: int, lo: int, hi: int] {
+        id: i32[id],
+        lo: i32[lo],
+        hi: i32[hi],
+    }
+    );
+
+    // A row invariant over two columns, each of which keeps it when assigned on its own.
+    rdiesel::policy! {
+        table schema::spans(Span) for User {
+            read: true,
+            update(lo: i32, hi: i32): true,
+            invariant: row.lo <= row.hi,
+            invariant(lo: i32): val <= row.hi,
+            invariant(hi: i32): row.lo <= val,
+        }
+    }
+
+    /// Adds the spans table to `conn`, with the single row `lo = 0, hi = 20`.
+    #[flux_rs::ignore]
+    fn with_spans(conn: &mut SqliteConnection) {
+        diesel::sql_query(
+            "CREATE TABLE spans (
+                id INTEGER PRIMARY KEY,
+                lo INTEGER NOT NULL,
+                hi INTEGER NOT NULL
+            )",
+        )
+        .execute(conn)
+        .expect("failed to create the schema");
+        diesel::sql_query("INSERT INTO spans VALUES (1, 0, 20)")
+            .execute(conn)
+            .expect("failed to seed the database");
+    }
+
+    #[flux_rs::ignore]
+    fn spans(conn: &mut SqliteConnection) -> Vec<Span> {
+        schema::spans::table
+            .load(conn)
+            .expect("failed to load the rows")
+    }
+
+    #[test]
+    #[flux_rs::ignore]
+    fn insert() {
+        let mut conn = establish();
+        let mut cx = context(&mut conn, 2);
+
+        let err = cx
+            .insert(NewWish {
+                owner: 1,
+                title: "drum".to_string(),
+                price: 80,
+                access_level: PUBLIC,
+            })
+            .unwrap_err();
+        drop(cx);
+
+        assert_eq!(violation(&err), Some(Operation::Insert));
+        assert_eq!(rows(&mut conn), seed());
+    }
+
+    #[test]
+    #[flux_rs::ignore]
+    fn insert_breaking_invariant() {
+        let mut conn = establish();
+        let mut cx = context(&mut conn, 1);
+
+        let err = cx
+            .insert(NewWish {
+                owner: 1,
+                title: "drum".to_string(),
+                price: -1,
+                access_level: PUBLIC,
+            })
+            .unwrap_err();
+        drop(cx);
+
+        assert_eq!(violation(&err), Some(Operation::Insert));
+        assert_eq!(rows(&mut conn), seed());
+    }
+
+    #[test]
+    #[flux_rs::ignore]
+    fn update_where() {
+        let mut conn = establish();
+        let mut cx = context(&mut conn, 2);
+
+        let err = cx
+            .update_where(wishes::id.eq(1), wishes::title.assign("yo-yo".to_string()))
+            .unwrap_err();
+        drop(cx);
+
+        assert_eq!(violation(&err), Some(Operation::Update));
+        assert_eq!(rows(&mut conn), seed());
+    }
+
+    #[test]
+    #[flux_rs::ignore]
+    fn update_where_breaking_invariant() {
+        let mut conn = establish();
+        let mut cx = context(&mut conn, 1);
+
+        let user = cx.auth_user();
+        let err = cx
+            .update_where(wishes::owner.eq(user.id), wishes::price.assign(-1))
+            .unwrap_err();
+        drop(cx);
+
+        assert_eq!(violation(&err), Some(Operation::Update));
+        assert_eq!(rows(&mut conn), seed());
+    }
+
+    #[test]
+    #[flux_rs::ignore]
+    fn delete_where() {
+        let mut conn = establish();
+        let mut cx = context(&mut conn, 2);
+
+        let err = cx
+            .delete_where(wishes::access_level.eq(PUBLIC))
+            .unwrap_err();
+        drop(cx);
+
+        assert_eq!(violation(&err), Some(Operation::Delete));
+        assert_eq!(rows(&mut conn), seed());
+    }
+
+    #[test]
+    #[flux_rs::ignore]
+    fn select_columns() {
+        let mut conn = establish();
+        let mut cx = context(&mut conn, 2);
+
+        let err = cx
+            .select_columns::<Wish, _, _, _, (i32, i32)>(
+                wishes::owner.eq(1),
+                (wishes::id, wishes::price),
+            )
+            .unwrap_err();
+        drop(cx);
+
+        assert_eq!(violation(&err), Some(Operation::Read));
+        assert_eq!(rows(&mut conn), seed());
+    }
+
+    /// The columns' invariants are checked against the old row, so assigning both columns of the
+    /// row invariant is denied, although each assignment keeps it on its own.
+    #[test]
+    #[flux_rs::ignore]
+    fn update_where_breaking_invariant_across_columns() {
+        use schema::spans;
+
+        let mut conn = establish();
+        with_spans(&mut conn);
+        let mut cx = context(&mut conn, 1);
+
+        let err = cx
+            .update_where(spans::id.eq(1), (spans::lo.assign(10), spans::hi.assign(5)))
+            .unwrap_err();
+        let updated = cx
+            .update_where(spans::id.eq(1), spans::lo.assign(10))
+            .unwrap();
+        drop(cx);
+
+        assert_eq!(violation(&err), Some(Operation::Update));
+        assert_eq!(updated, 1);
+        assert_eq!(
+            spans(&mut conn),
+            vec![Span {
+                id: 1,
+                lo: 10,
+                hi: 20,
+            }],
+        );
+    }
+
+    /// The filters that `update_where` and `delete_where` conjoin into the `WHERE` clause of the
+    /// write, so that rows committed after the check are only written if the policies allow it.
+    #[test]
+    #[flux_rs::ignore]
+    fn write_filters() {
+        let user = User { id: 2 };
+        let update = (
+            wishes::title.assign("yo-yo".to_string()),
+            wishes::price.assign(5),
+        );
+        let updatable = update.update_filter(&user);
+        let breaking = wishes::price.assign(-1).update_filter(&user);
+        let deletable = <Wish as DeleteFilter<User>>::delete_filter(&user);
+
+        assert_eq!(
+            expected(|w| updatable.matches(w)),
+            expected(|w| w.owner == 2),
+        );
+        assert_eq!(expected(|w| breaking.matches(w)), vec![]);
+        assert_eq!(
+            expected(|w| deletable.matches(w)),
+            expected(|w| w.owner == 2),
+        );
+    }
+}
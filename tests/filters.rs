@@ -0,0 +1,114 @@
+//! The read policies that the guards conjoin into the `WHERE` clause with `runtime-checks`, and
+//! their conservative translation when a policy goes beyond the `Expr` DSL.
+#![cfg(feature = "runtime-checks")]
+mod common;
+
+use common::{context, establish, models::*, seed, PUBLIC};
+use diesel::{sqlite::Sqlite, RunQueryDsl, SqliteConnection};
+use flux_rs::*;
+use rdiesel::{debug, runtime::RowPolicy, RdieselModel};
+
+defs! {
+    // `pinned(priority)` holds when a note of that priority is pinned for everyone to read.
+    fn pinned(priority: int) -> bool;
+}
+
+/// The Rust counterpart of `pinned`, used by the checks on loaded rows.
+fn pinned(priority: i32) -> bool {
+    priority > 5
+}
+
+#[trusted]
+mod schema {
+    diesel::table! {
+        notes (id) {
+            id -> Integer,
+            author -> Integer,
+            priority -> Integer,
+        }
+    }
+}
+
+flux!(
+#[derive(Clone, Debug, PartialEq, diesel::Queryable, diesel::Selectable, diesel::Identifiable, RdieselModel)]
+#[diesel(table_name = schema::notes)]
+struct Note[id: int, author: int, priority: int] {
+    id: i32[id],
+    author: i32[author],
+    priority: i32[priority],
+}
+);
+
+rdiesel::policy! {
+    table schema::notes(Note) for User {
+        read: user.id == row.author || pinned(row.priority),
+    }
+}
+
+/// Adds the notes table to `conn`: user 1 wrote note 1, user 2 wrote the pinned note 2 and the
+/// unpinned note 3.
+#[flux_rs::ignore]
+fn with_notes(conn: &mut SqliteConnection) {
+    diesel::sql_query(
+        "CREATE TABLE notes (
+            id INTEGER PRIMARY KEY,
+            author INTEGER NOT NULL,
+            priority INTEGER NOT NULL
+        )",
+    )
+    .execute(conn)
+    .expect("failed to create the schema");
+    diesel::sql_query("INSERT INTO notes VALUES (1, 1, 0), (2, 2, 9), (3, 2, 0)")
+        .execute(conn)
+        .expect("failed to seed the database");
+}
+
+#[test]
+fn read_filter_is_conjoined() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 2);
+
+    let user = cx.auth_user();
+    let sql = debug::select_list::<Wish, Sqlite, _, _>(&user, true);
+    let found: Vec<Wish> = cx.select_list(true).unwrap();
+
+    assert!(
+        sql.ends_with(
+            "FROM `wishes` WHERE (? AND ((`wishes`.`owner` = ?) OR \
+             (`wishes`.`access_level` = ?))) -- binds: [true, 2, 0]"
+        ),
+        "{sql}"
+    );
+    let readable = seed()
+        .into_iter()
+        .filter(|w| w.owner == 2 || w.access_level == PUBLIC);
+    assert_eq!(found, readable.collect::<Vec<_>>());
+}
+
+/// `pinned` has no SQL counterpart, so the filter replaces it by `false` and the pinned note of
+/// another user is not loaded, although the policy allows reading it.
+#[test]
+fn fallback_excludes_rows() {
+    let mut conn = establish();
+    with_notes(&mut conn);
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let sql = debug::select_list::<Note, Sqlite, _, _>(&user, true);
+    let found: Vec<Note> = cx.select_list(true).unwrap();
+
+    assert!(
+        sql.ends_with(
+            "FROM `notes` WHERE (? AND ((`notes`.`author` = ?) OR ?)) \
+             -- binds: [true, 1, false]"
+        ),
+        "{sql}"
+    );
+    let note = |id, author, priority| Note {
+        id,
+        author,
+        priority,
+    };
+    assert!(note(2, 2, 9).allow_read(&user));
+    assert_eq!(found, vec![note(1, 1, 0)]);
+}
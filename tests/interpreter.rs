@@ -0,0 +1,81 @@
+//! [`ExprRuntime`] against the rows SQLite returns for the same filters.
+mod common;
+
+use common::{context, establish, models::*, schema::wishes, seed, PRIVATE, PUBLIC};
+use rdiesel::{interpreter::ExprRuntime, Expr, ValueSet};
+
+/// The seed rows that `filter` matches in memory.
+fn matching(filter: &impl ExprRuntime<Wish, Value = bool>) -> Vec<Wish> {
+    seed().into_iter().filter(|w| filter.matches(w)).collect()
+}
+
+#[test]
+fn columns() {
+    let bike = &seed()[0];
+
+    assert_eq!(wishes::id.eval(bike), Some(1));
+    assert_eq!(wishes::title.eval(bike), Some("bike".to_string()));
+    assert_eq!(wishes::price.gt(100).eval(bike), Some(true));
+}
+
+#[test]
+fn and_or() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = wishes::owner
+        .eq(user.id)
+        .and(wishes::price.lt(100).or(wishes::access_level.eq(PRIVATE)));
+    let expected = matching(&filter);
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn eq_any() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 2);
+
+    let owners: ValueSet<i32> = [2, 3].into_iter().collect();
+    let filter = wishes::access_level.eq(PUBLIC).and(
+        wishes::owner
+            .eq_any(owners)
+            .or(wishes::price.between(100, 500)),
+    );
+    let expected = matching(&filter);
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn string_literals() {
+    let mut conn = establish();
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = wishes::owner.eq(user.id).and(wishes::title.ne("kite"));
+    let expected = matching(&filter);
+    let found: Vec<Wish> = cx.select_list(filter).unwrap();
+
+    assert_eq!(found, expected);
+}
+
+#[test]
+fn three_valued_logic() {
+    let bike = &seed()[0];
+    let unknown = wishes::price.eq(None::<i32>);
+
+    assert_eq!(unknown.clone().eval(bike), None);
+    assert_eq!(unknown.clone().not().eval(bike), None);
+    assert_eq!(unknown.clone().and(false).eval(bike), Some(false));
+    assert_eq!(unknown.clone().or(true).eval(bike), Some(true));
+    assert_eq!(unknown.clone().or(false).eval(bike), None);
+    assert_eq!(
+        wishes::price.between(None::<i32>, 100).eval(bike),
+        Some(false)
+    );
+    assert!(!unknown.matches(bike));
+}
@@ -0,0 +1,374 @@
+//! Literals of the other value types that `Expr` supports, compared against columns of the
+//! matching SQL types on SQLite and checked like `context.rs`. SQLite has no UUID type, so UUID
+//! literals are only checked against the SQL generated for PostgreSQL.
+mod common;
+
+use common::{context, establish, models::User, where_clause};
+use diesel::{sqlite::Sqlite, RunQueryDsl, SqliteConnection};
+use flux_rs::*;
+use rdiesel::{debug, Expr, RdieselModel};
+
+/// The SQL that `select_list` runs on SQLite, where `columns` is the `SELECT` of the table, given
+/// a filter that renders as `filter` with `binds`. The tables below can be read by everyone, which the guards
+/// conjoin as a bound `true` under `runtime-checks`.
+fn select_sql(columns: &str, filter: &str, binds: &str) -> String {
+    format!("{columns} {}", where_clause(filter, binds, "?", "true"))
+}
+
+#[trusted]
+mod schema {
+    diesel::table! {
+        readings (id) {
+            id -> Integer,
+            small -> SmallInt,
+            big -> BigInt,
+            ratio -> Float,
+            amount -> Double,
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    diesel::table! {
+        chrono_events (id) {
+            id -> Integer,
+            at -> Timestamp,
+        }
+    }
+
+    #[cfg(feature = "time")]
+    diesel::table! {
+        time_events (id) {
+            id -> Integer,
+            at -> Timestamp,
+        }
+    }
+
+    #[cfg(feature = "uuid")]
+    diesel::table! {
+        tokens (id) {
+            id -> Uuid,
+        }
+    }
+}
+
+use schema::readings;
+
+flux!(
+#[derive(Clone, Debug, PartialEq, diesel::Queryable, diesel::Selectable, diesel::Identifiable, RdieselModel)]
+#[diesel(table_name = schema::readings)]
+struct Reading[id: int, small: int, big: int] {
+    id: i32[id],
+    small: i16[small],
+    big: i64[big],
+    ratio: f32,
+    amount: f64,
+}
+);
+
+rdiesel::policy! {
+    table schema::readings(Reading) for User {
+        read: true,
+    }
+}
+
+const READINGS: &str = "SELECT `readings`.`id`, `readings`.`small`, `readings`.`big`, \
+                        `readings`.`ratio`, `readings`.`amount` FROM `readings`";
+
+fn seed() -> Vec<Reading> {
+    let reading = |id, small, big, ratio, amount| Reading {
+        id,
+        small,
+        big,
+        ratio,
+        amount,
+    };
+    vec![
+        reading(1, 1, 5_000_000_000, 0.25, 10.5),
+        reading(2, 2, 7, 0.75, 99.5),
+        reading(3, 1, -3_000_000_000, 1.5, 60.0),
+    ]
+}
+
+/// Adds the readings table with the [`seed`] rows to `conn`.
+#[flux_rs::ignore]
+fn with_readings(conn: &mut SqliteConnection) {
+    diesel::sql_query(
+        "CREATE TABLE readings (
+            id INTEGER PRIMARY KEY,
+            small SMALLINT NOT NULL,
+            big BIGINT NOT NULL,
+            ratio FLOAT NOT NULL,
+            amount DOUBLE NOT NULL
+        )",
+    )
+    .execute(conn)
+    .expect("failed to create the schema");
+    for r in seed() {
+        diesel::sql_query("INSERT INTO readings VALUES (?, ?, ?, ?, ?)")
+            .bind::<diesel::sql_types::Integer, _>(r.id)
+            .bind::<diesel::sql_types::SmallInt, _>(r.small)
+            .bind::<diesel::sql_types::BigInt, _>(r.big)
+            .bind::<diesel::sql_types::Float, _>(r.ratio)
+            .bind::<diesel::sql_types::Double, _>(r.amount)
+            .execute(conn)
+            .expect("failed to seed the database");
+    }
+}
+
+#[test]
+fn integers() {
+    let mut conn = establish();
+    with_readings(&mut conn);
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = readings::small
+        .eq(1_i16)
+        .and(readings::big.gt(1_000_000_000_i64));
+    let sql = debug::select_list::<Reading, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Reading> = cx.select_list(filter).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(
+            READINGS,
+            "((`readings`.`small` = ?) AND (`readings`.`big` > ?))",
+            "1, 1000000000",
+        ),
+    );
+    let expected = seed()
+        .into_iter()
+        .filter(|r| r.small == 1 && r.big > 1_000_000_000);
+    assert_eq!(found, expected.collect::<Vec<_>>());
+}
+
+#[test]
+fn floats() {
+    let mut conn = establish();
+    with_readings(&mut conn);
+    let mut cx = context(&mut conn, 1);
+
+    let user = cx.auth_user();
+    let filter = readings::ratio
+        .lt(1.0_f32)
+        .and(readings::amount.ge(50.0_f64));
+    let sql = debug::select_list::<Reading, Sqlite, _, _>(&user, filter.clone());
+    let found: Vec<Reading> = cx.select_list(filter).unwrap();
+
+    assert_eq!(
+        sql,
+        select_sql(
+            READINGS,
+            "((`readings`.`ratio` < ?) AND (`readings`.`amount` >= ?))",
+            "1.0, 50.0",
+        ),
+    );
+    let expected = seed()
+        .into_iter()
+        .filter(|r| r.ratio < 1.0 && r.amount >= 50.0);
+    assert_eq!(found, expected.collect::<Vec<_>>());
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_literals {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use diesel::{sqlite::Sqlite, RunQueryDsl, SqliteConnection};
+    use flux_rs::*;
+    use rdiesel::{debug, Expr, RdieselModel};
+
+    use super::{context, establish, schema, schema::chrono_events, select_sql, User};
+
+    flux!(
+    #[derive(Clone, Debug, PartialEq, diesel::Queryable, diesel::Selectable, diesel::Identifiable, RdieselModel)]
+    #[diesel(table_name = schema::chrono_events)]
+    struct Event[id: int] {
+        id: i32[id],
+        at: NaiveDateTime,
+    }
+    );
+
+    rdiesel::policy! {
+        table schema::chrono_events(Event) for User {
+            read: true,
+        }
+    }
+
+    fn at(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .and_then(|date| date.and_hms_opt(12, 0, 0))
+            .unwrap()
+    }
+
+    fn seed() -> Vec<Event> {
+        (1..=3)
+            .map(|id| Event {
+                id,
+                at: at(id as u32),
+            })
+            .collect()
+    }
+
+    /// Adds the events table with the [`seed`] rows to `conn`.
+    #[flux_rs::ignore]
+    fn with_events(conn: &mut SqliteConnection) {
+        diesel::sql_query(
+            "CREATE TABLE chrono_events (id INTEGER PRIMARY KEY, at TIMESTAMP NOT NULL)",
+        )
+        .execute(conn)
+        .expect("failed to create the schema");
+        for e in seed() {
+            diesel::sql_query("INSERT INTO chrono_events VALUES (?, ?)")
+                .bind::<diesel::sql_types::Integer, _>(e.id)
+                .bind::<diesel::sql_types::Timestamp, _>(e.at)
+                .execute(conn)
+                .expect("failed to seed the database");
+        }
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut conn = establish();
+        with_events(&mut conn);
+        let mut cx = context(&mut conn, 1);
+
+        let user = cx.auth_user();
+        let filter = chrono_events::at.gt(at(1));
+        let sql = debug::select_list::<Event, Sqlite, _, _>(&user, filter.clone());
+        let found: Vec<Event> = cx.select_list(filter).unwrap();
+
+        assert_eq!(
+            sql,
+            select_sql(
+                "SELECT `chrono_events`.`id`, `chrono_events`.`at` FROM `chrono_events`",
+                "(`chrono_events`.`at` > ?)",
+                &format!("{:?}", at(1)),
+            ),
+        );
+        let expected = seed().into_iter().filter(|e| e.at > at(1));
+        assert_eq!(found, expected.collect::<Vec<_>>());
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_literals {
+    use diesel::{sqlite::Sqlite, RunQueryDsl, SqliteConnection};
+    use flux_rs::*;
+    use rdiesel::{debug, Expr, RdieselModel};
+    use time::{Date, Month, PrimitiveDateTime, Time};
+
+    use super::{context, establish, schema, schema::time_events, select_sql, User};
+
+    flux!(
+    #[derive(Clone, Debug, PartialEq, diesel::Queryable, diesel::Selectable, diesel::Identifiable, RdieselModel)]
+    #[diesel(table_name = schema::time_events)]
+    struct Event[id: int] {
+        id: i32[id],
+        at: PrimitiveDateTime,
+    }
+    );
+
+    rdiesel::policy! {
+        table schema::time_events(Event) for User {
+            read: true,
+        }
+    }
+
+    fn at(day: u8) -> PrimitiveDateTime {
+        let date = Date::from_calendar_date(2024, Month::January, day).unwrap();
+        PrimitiveDateTime::new(date, Time::from_hms(12, 0, 0).unwrap())
+    }
+
+    fn seed() -> Vec<Event> {
+        (1..=3)
+            .map(|id| Event {
+                id,
+                at: at(id as u8),
+            })
+            .collect()
+    }
+
+    /// Adds the events table with the [`seed`] rows to `conn`.
+    #[flux_rs::ignore]
+    fn with_events(conn: &mut SqliteConnection) {
+        diesel::sql_query(
+            "CREATE TABLE time_events (id INTEGER PRIMARY KEY, at TIMESTAMP NOT NULL)",
+        )
+        .execute(conn)
+        .expect("failed to create the schema");
+        for e in seed() {
+            diesel::sql_query("INSERT INTO time_events VALUES (?, ?)")
+                .bind::<diesel::sql_types::Integer, _>(e.id)
+                .bind::<diesel::sql_types::Timestamp, _>(e.at)
+                .execute(conn)
+                .expect("failed to seed the database");
+        }
+    }
+
+    #[test]
+    fn timestamps() {
+        let mut conn = establish();
+        with_events(&mut conn);
+        let mut cx = context(&mut conn, 1);
+
+        let user = cx.auth_user();
+        let filter = time_events::at.le(at(2));
+        let sql = debug::select_list::<Event, Sqlite, _, _>(&user, filter.clone());
+        let found: Vec<Event> = cx.select_list(filter).unwrap();
+
+        assert_eq!(
+            sql,
+            select_sql(
+                "SELECT `time_events`.`id`, `time_events`.`at` FROM `time_events`",
+                "(`time_events`.`at` <= ?)",
+                &format!("{:?}", at(2)),
+            ),
+        );
+        let expected = seed().into_iter().filter(|e| e.at <= at(2));
+        assert_eq!(found, expected.collect::<Vec<_>>());
+    }
+}
+
+#[cfg(feature = "uuid")]
+mod uuid_literals {
+    use diesel::pg::Pg;
+    use flux_rs::*;
+    use rdiesel::{debug, Expr, RdieselModel};
+    use uuid::Uuid;
+
+    use super::{schema, schema::tokens, where_clause, User};
+
+    flux!(
+        #[derive(
+            Clone,
+            Debug,
+            PartialEq,
+            diesel::Queryable,
+            diesel::Selectable,
+            diesel::Identifiable,
+            RdieselModel,
+        )]
+        #[diesel(table_name = schema::tokens)]
+        struct Token {
+            id: Uuid,
+        }
+    );
+
+    rdiesel::policy! {
+        table schema::tokens(Token) for User {
+            read: true,
+        }
+    }
+
+    #[test]
+    fn uuids() {
+        let user = User { id: 1 };
+        let id = Uuid::from_u128(0x67e5_5044_10b1_426f_9247_bb68_0e5f_e0c8);
+        let sql = debug::select_list::<Token, Pg, _, _>(&user, tokens::id.eq(id));
+
+        let clause = where_clause("(\"tokens\".\"id\" = $1)", &id.to_string(), "$2", "true");
+        assert_eq!(
+            sql,
+            format!("SELECT \"tokens\".\"id\" FROM \"tokens\" {clause}")
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! The row-level security migration generated from the policies of the test schema.
+// The policies below negate a comparison on purpose, to exercise the translation of `!`.
+#![allow(clippy::nonminimal_bool)]
+mod common;
+
+use common::{models::User, schema::wishes};
+use flux_rs::*;
+use rdiesel::{
+    rls::{Migration, RowLevelSecurity, UserSettings},
+    RdieselModel,
+};
+
+#[trusted]
+mod schema {
+    diesel::table! {
+        #[sql_name = "accounts"]
+        auth.users (id) {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    diesel::table! {
+        notes (id) {
+            id -> Integer,
+            author -> Integer,
+            tag -> Text,
+            priority -> Integer,
+        }
+    }
+}
+
+flux!(
+#[allow(dead_code)]
+#[derive(Clone, Debug, diesel::Queryable, diesel::Selectable, RdieselModel)]
+#[diesel(table_name = schema::notes)]
+struct Note[id: int, author: int, tag: str, priority: int] {
+    id: i32[id],
+    author: i32[author],
+    #[rdiesel(index = tag)]
+    tag: String[tag],
+    priority: i32[priority],
+}
+);
+
+// Policies whose translation needs quoting or falls back to a constant, as they compare the
+// assigned value, which the database does not know.
+rdiesel::policy! {
+    table schema::notes(Note) for User {
+        read: row.tag != "don't" || user.id == row.author,
+        update(tag: String): user.id == row.author || val == row.tag,
+        update(priority: i32): user.id == row.author && !(val > row.priority),
+        delete: user.id == row.author,
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug, diesel::Queryable, diesel::Selectable, RdieselModel)]
+#[diesel(table_name = schema::users)]
+struct Account {
+    id: i32,
+    name: String,
+}
+
+// Only columns can be read, so the rows they are read from must be visible.
+rdiesel::policy! {
+    table schema::users(Account) for User {
+        read(id: i32): true,
+        read(name: String): user.id == row.id,
+    }
+}
+
+/// `user.id`, as read from the default settings.
+const USER_ID: &str = "NULLIF(current_setting('app.user_id', true), '')::integer";
+
+#[test]
+fn up_sql() {
+    let migration = Migration::new(UserSettings::new()).table::<wishes::table>();
+
+    assert_eq!(
+        migration.up_sql(),
+        format!(
+            "ALTER TABLE \"wishes\" ENABLE ROW LEVEL SECURITY;\n\
+             ALTER TABLE \"wishes\" FORCE ROW LEVEL SECURITY;\n\
+             CREATE POLICY rdiesel_select ON \"wishes\" FOR SELECT \
+             USING (((({USER_ID} = \"owner\") OR (\"access_level\" = 0)) OR (TRUE)));\n\
+             CREATE POLICY rdiesel_insert ON \"wishes\" FOR INSERT \
+             WITH CHECK (({USER_ID} = \"owner\"));\n\
+             CREATE POLICY rdiesel_update ON \"wishes\" FOR UPDATE \
+             USING (((FALSE) OR (({USER_ID} = \"owner\"))));\n\
+             CREATE POLICY rdiesel_delete ON \"wishes\" FOR DELETE \
+             USING (({USER_ID} = \"owner\"));\n"
+        ),
+    );
+}
+
+#[test]
+fn down_sql() {
+    let migration = Migration::new(UserSettings::new()).table::<wishes::table>();
+
+    assert_eq!(
+        migration.down_sql(),
+        "DROP POLICY IF EXISTS rdiesel_select ON \"wishes\";\n\
+         DROP POLICY IF EXISTS rdiesel_insert ON \"wishes\";\n\
+         DROP POLICY IF EXISTS rdiesel_update ON \"wishes\";\n\
+         DROP POLICY IF EXISTS rdiesel_delete ON \"wishes\";\n\
+         ALTER TABLE \"wishes\" NO FORCE ROW LEVEL SECURITY;\n\
+         ALTER TABLE \"wishes\" DISABLE ROW LEVEL SECURITY;\n",
+    );
+}
+
+/// String literals are quoted, and parts of a policy that mention the assigned value become
+/// `FALSE`, or `TRUE` under a negation.
+#[test]
+fn quoting_and_fallback() {
+    let user = UserSettings::new()
+        .prefix("rdiesel.")
+        .field_type("id", "bigint");
+    let user_id = "NULLIF(current_setting('rdiesel.id', true), '')::bigint";
+    let migration = Migration::new(user).table::<schema::notes::table>();
+
+    assert_eq!(
+        migration.up_sql(),
+        format!(
+            "ALTER TABLE \"notes\" ENABLE ROW LEVEL SECURITY;\n\
+             ALTER TABLE \"notes\" FORCE ROW LEVEL SECURITY;\n\
+             CREATE POLICY rdiesel_select ON \"notes\" FOR SELECT \
+             USING (((\"tag\" <> 'don''t') OR ({user_id} = \"author\")));\n\
+             CREATE POLICY rdiesel_update ON \"notes\" FOR UPDATE \
+             USING (((({user_id} = \"author\") OR FALSE) OR \
+             (({user_id} = \"author\") AND NOT (TRUE))));\n\
+             CREATE POLICY rdiesel_delete ON \"notes\" FOR DELETE \
+             USING (({user_id} = \"author\"));\n"
+        ),
+    );
+}
+
+/// Tables are named as in SQL, and a table whose columns can be read has a `SELECT` policy even
+/// without a `read` clause of its own.
+#[test]
+fn sql_name_and_column_reads() {
+    let migration = Migration::new(UserSettings::new()).table::<schema::users::table>();
+
+    assert_eq!(
+        migration.up_sql(),
+        format!(
+            "ALTER TABLE \"auth\".\"accounts\" ENABLE ROW LEVEL SECURITY;\n\
+             ALTER TABLE \"auth\".\"accounts\" FORCE ROW LEVEL SECURITY;\n\
+             CREATE POLICY rdiesel_select ON \"auth\".\"accounts\" FOR SELECT \
+             USING (((TRUE) OR (({USER_ID} = \"id\"))));\n"
+        ),
+    );
+    assert_eq!(
+        migration.down_sql(),
+        "DROP POLICY IF EXISTS rdiesel_select ON \"auth\".\"accounts\";\n\
+         ALTER TABLE \"auth\".\"accounts\" NO FORCE ROW LEVEL SECURITY;\n\
+         ALTER TABLE \"auth\".\"accounts\" DISABLE ROW LEVEL SECURITY;\n",
+    );
+}
+
+/// Tables are set up in order and torn down in reverse.
+#[test]
+fn table_order() {
+    let user = UserSettings::new();
+    let migration = Migration::new(user.clone())
+        .table::<wishes::table>()
+        .table::<schema::notes::table>();
+    let wishes = wishes::table::row_level_security(&user);
+    let notes = schema::notes::table::row_level_security(&user);
+
+    assert_eq!(
+        migration.up_sql(),
+        format!("{}\n{}", wishes.up_sql(), notes.up_sql()),
+    );
+    assert_eq!(
+        migration.down_sql(),
+        format!("{}\n{}", notes.down_sql(), wishes.down_sql()),
+    );
+}